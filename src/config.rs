@@ -1,6 +1,18 @@
+use crate::models::{
+    row_version, AddColumnRequest, CellValue, ColumnChange, CreateColumnRequest,
+    CreateIndexRequest, CreateTableRequest, CreateViewRequest, DeleteRequest, DropColumnRequest,
+    DropIndexRequest, ModifyColumnRequest, QualifiedName, RenameColumnRequest, TableStats,
+    UpdateRequest, ViewInfo,
+};
+use crate::query_builder::{self, QueryBuilder};
+use crate::statement::{self, StatementKind};
 use rocket::fairing::AdHoc;
 use serde::{Deserialize, Serialize};
-use sqlx::{AnyPool, Column, Row};
+use sqlx::any::{AnyPoolOptions, AnyRow};
+use sqlx::pool::PoolConnection;
+use sqlx::{Any, AnyConnection, AnyPool, Column, Connection, Row, TypeInfo, ValueRef};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
 use url::Url;
 
 pub struct DatabasePool(pub AnyPool);
@@ -19,6 +31,172 @@ pub enum DatabaseType {
     Postgres,
 }
 
+impl DatabaseType {
+    /// Quote an identifier using the dialect's native quoting style.
+    pub fn quote_identifier(&self, identifier: &str) -> String {
+        match self {
+            DatabaseType::Mysql => format!("`{}`", identifier.replace('`', "``")),
+            DatabaseType::Sqlite | DatabaseType::Postgres => {
+                format!("\"{}\"", identifier.replace('"', "\"\""))
+            }
+        }
+    }
+
+    /// Quote a possibly schema-qualified table name.
+    pub fn quote_qualified(&self, name: &QualifiedName) -> String {
+        match &name.schema {
+            Some(schema) => format!(
+                "{}.{}",
+                self.quote_identifier(schema),
+                self.quote_identifier(&name.name)
+            ),
+            None => self.quote_identifier(&name.name),
+        }
+    }
+
+    /// Bind placeholder for the 1-based parameter `index`.
+    pub fn placeholder(&self, index: usize) -> String {
+        match self {
+            DatabaseType::Postgres => format!("${}", index),
+            DatabaseType::Sqlite | DatabaseType::Mysql => "?".to_string(),
+        }
+    }
+
+    /// Quote a string literal, for the few places (DDL defaults) where
+    /// values can't be bound.
+    pub fn quote_literal(&self, value: &str) -> String {
+        match self {
+            // MySQL treats backslashes in string literals as escapes by default.
+            DatabaseType::Mysql => {
+                format!("'{}'", value.replace('\\', "\\\\").replace('\'', "''"))
+            }
+            DatabaseType::Sqlite | DatabaseType::Postgres => {
+                format!("'{}'", value.replace('\'', "''"))
+            }
+        }
+    }
+
+    /// Physical row id pseudo-column used when a table has no primary key,
+    /// with its type and the expression selecting it as text.
+    pub fn row_id_column(&self) -> Option<(&'static str, &'static str, &'static str)> {
+        match self {
+            DatabaseType::Sqlite => Some(("rowid", "INTEGER", "rowid")),
+            DatabaseType::Postgres => Some(("ctid", "tid", "ctid::text")),
+            DatabaseType::Mysql => None,
+        }
+    }
+
+    /// Common column types offered by the table designer.
+    pub fn column_types(&self) -> &'static [&'static str] {
+        match self {
+            DatabaseType::Sqlite => &["INTEGER", "TEXT", "REAL", "NUMERIC", "BLOB"],
+            DatabaseType::Mysql => &[
+                "INT",
+                "BIGINT",
+                "TINYINT(1)",
+                "DECIMAL(10,2)",
+                "DOUBLE",
+                "VARCHAR(255)",
+                "TEXT",
+                "DATE",
+                "DATETIME",
+                "TIMESTAMP",
+                "JSON",
+                "BLOB",
+            ],
+            DatabaseType::Postgres => &[
+                "integer",
+                "bigint",
+                "boolean",
+                "numeric(10,2)",
+                "double precision",
+                "varchar(255)",
+                "text",
+                "date",
+                "timestamp",
+                "timestamptz",
+                "uuid",
+                "jsonb",
+                "bytea",
+            ],
+        }
+    }
+
+    /// SQL for a column default entered in a form. Defaults already written
+    /// as SQL are kept: numbers, NULL, booleans, the current date/time
+    /// keywords and quoted strings, each optionally with a Postgres cast, and
+    /// expressions ending in a parenthesis such as `now()`. SQLite and MySQL
+    /// only take expressions in parentheses, so those are wrapped unless they
+    /// already are. Anything else is a string.
+    pub fn default_expression(&self, value: &str) -> String {
+        let value = value.trim();
+        let bare = statement::without_casts(value);
+        let keyword = bare.to_ascii_uppercase();
+        let is_keyword = matches!(
+            keyword.as_str(),
+            "NULL" | "TRUE" | "FALSE" | "CURRENT_TIMESTAMP" | "CURRENT_DATE" | "CURRENT_TIME"
+        );
+        let is_string = bare.len() >= 2 && bare.starts_with('\'') && bare.ends_with('\'');
+        if is_keyword || is_string || bare.parse::<f64>().is_ok() {
+            value.to_string()
+        } else if !bare.ends_with(')') {
+            self.quote_literal(value)
+        } else if value.starts_with('(') && value.ends_with(')') && bare != value {
+            value.to_string()
+        } else {
+            format!("({})", value)
+        }
+    }
+
+    /// Whether a default entered in a form is the column's `current` one.
+    /// Postgres reports defaults with casts, as in `'x'::text`, and MySQL
+    /// reports literal defaults unquoted, so both sides are compared as SQL
+    /// without casts.
+    pub fn same_default(&self, entered: &str, current: &str) -> bool {
+        let current = match self {
+            DatabaseType::Mysql => self.default_expression(current),
+            DatabaseType::Sqlite | DatabaseType::Postgres => current.to_string(),
+        };
+        let entered = self.default_expression(entered);
+        statement::without_casts(&entered) == statement::without_casts(&current)
+    }
+
+    /// Definition of a new column, as used in CREATE TABLE and ADD COLUMN.
+    /// A SQLite auto-increment column must be the INTEGER PRIMARY KEY, so it
+    /// carries its primary key inline.
+    pub fn column_definition(&self, column: &CreateColumnRequest) -> String {
+        let mut sql = format!(
+            "{} {}",
+            self.quote_identifier(&column.name),
+            column.data_type
+        );
+        if column.auto_increment {
+            match self {
+                DatabaseType::Sqlite => {
+                    sql = format!(
+                        "{} INTEGER PRIMARY KEY AUTOINCREMENT",
+                        self.quote_identifier(&column.name)
+                    )
+                }
+                DatabaseType::Mysql => sql += " AUTO_INCREMENT",
+                DatabaseType::Postgres => sql += " GENERATED BY DEFAULT AS IDENTITY",
+            }
+        }
+        if !column.nullable {
+            sql += " NOT NULL";
+        }
+        if let Some(ref default_value) = column.default_value
+            && !default_value.is_empty()
+        {
+            sql += &format!(" DEFAULT {}", self.default_expression(default_value));
+        }
+        if column.unique {
+            sql += " UNIQUE";
+        }
+        sql
+    }
+}
+
 impl DatabaseConfig {
     pub fn from_url(url: &str) -> Result<Self, Box<dyn std::error::Error>> {
         let parsed_url = Url::parse(url)?;
@@ -41,12 +219,38 @@ impl DatabaseConfig {
         })
     }
 
-    #[allow(unused)]
+    /// URL the pool connects with. In readonly mode SQLite databases are
+    /// opened read-only.
     pub fn get_connection_url(&self) -> String {
-        self.url.clone()
+        if !self.readonly || !matches!(self.database_type, DatabaseType::Sqlite) {
+            return self.url.clone();
+        }
+
+        let (base, query) = self.url.split_once('?').unwrap_or((&self.url, ""));
+        let mut params: Vec<&str> = query
+            .split('&')
+            .filter(|p| !p.is_empty() && !p.starts_with("mode="))
+            .collect();
+        params.push("mode=ro");
+        format!("{}?{}", base, params.join("&"))
     }
 }
 
+/// Make the connection itself refuse writes, whatever statement gets past
+/// the checks in the routes.
+async fn enforce_readonly(
+    conn: &mut AnyConnection,
+    database_type: &DatabaseType,
+) -> Result<(), sqlx::Error> {
+    let sql = match database_type {
+        DatabaseType::Sqlite => "PRAGMA query_only = ON",
+        DatabaseType::Mysql => "SET SESSION TRANSACTION READ ONLY",
+        DatabaseType::Postgres => "SET default_transaction_read_only = on",
+    };
+    sqlx::query(sql).execute(conn).await?;
+    Ok(())
+}
+
 impl DatabasePool {
     pub fn init() -> AdHoc {
         AdHoc::on_ignite("SQLx Database", |rocket| async {
@@ -57,7 +261,28 @@ impl DatabasePool {
                 .state::<DatabaseConfig>()
                 .expect("DatabaseConfig not managed");
 
-            let pool = AnyPool::connect(&config.url)
+            let mut options = AnyPoolOptions::new();
+            if config.readonly {
+                // Statements can change session settings, so read-only mode is
+                // applied again every time a connection is handed out.
+                let database_type = config.database_type.clone();
+                let on_acquire = database_type.clone();
+                options = options
+                    .after_connect(move |conn, _| {
+                        let database_type = database_type.clone();
+                        Box::pin(async move { enforce_readonly(conn, &database_type).await })
+                    })
+                    .before_acquire(move |conn, _| {
+                        let database_type = on_acquire.clone();
+                        Box::pin(async move {
+                            enforce_readonly(conn, &database_type).await?;
+                            Ok(true)
+                        })
+                    });
+            }
+
+            let pool = options
+                .connect(&config.get_connection_url())
                 .await
                 .expect("Failed to connect to database");
 
@@ -92,11 +317,202 @@ impl DatabaseInfo {
 pub struct DatabaseManager<'a> {
     pool: &'a AnyPool,
     config: DatabaseConfig,
+    /// Postgres schema being browsed; `None` follows the search_path.
+    schema: Option<String>,
 }
 
 impl<'a> DatabaseManager<'a> {
     pub fn new(pool: &'a AnyPool, config: DatabaseConfig) -> Self {
-        Self { pool, config }
+        Self {
+            pool,
+            config,
+            schema: None,
+        }
+    }
+
+    /// Browse the given Postgres schema. Other databases have no schemas
+    /// within a database, so it is ignored there.
+    pub fn with_schema(mut self, schema: Option<String>) -> Self {
+        if matches!(self.config.database_type, DatabaseType::Postgres) {
+            self.schema = schema;
+        }
+        self
+    }
+
+    /// `name` qualified by the schema being browsed.
+    pub fn qualify(&self, name: &str) -> QualifiedName {
+        QualifiedName::new(self.schema.clone(), name)
+    }
+
+    /// Condition restricting the `pg_class` rows aliased `class` to the
+    /// schema of `table`, or to relations visible through the search_path.
+    fn pg_namespace_condition(&self, table: &QualifiedName, class: &str) -> String {
+        match &table.schema {
+            Some(schema) => format!(
+                "{}.relnamespace = (SELECT oid FROM pg_namespace WHERE nspname = {})",
+                class,
+                self.config.database_type.quote_literal(schema)
+            ),
+            None => format!("pg_table_is_visible({}.oid)", class),
+        }
+    }
+
+    /// Condition restricting `pg_namespace` rows aliased `namespace` to the
+    /// schema being browsed, or the current schema.
+    fn pg_schema_condition(&self, namespace: &str) -> String {
+        match &self.schema {
+            Some(schema) => format!(
+                "{}.nspname = {}",
+                namespace,
+                self.config.database_type.quote_literal(schema)
+            ),
+            None => format!("{}.nspname = current_schema()", namespace),
+        }
+    }
+
+    /// Schemas holding user objects; empty where the database has no
+    /// schemas to choose from.
+    pub async fn get_schemas(&self) -> Result<Vec<String>, sqlx::Error> {
+        match self.config.database_type {
+            DatabaseType::Postgres => {
+                sqlx::query_scalar(
+                    r#"
+                    SELECT nspname::text FROM pg_namespace
+                    WHERE nspname NOT LIKE 'pg\_%' AND nspname <> 'information_schema'
+                    ORDER BY nspname
+                    "#,
+                )
+                .fetch_all(self.pool)
+                .await
+            }
+            DatabaseType::Sqlite | DatabaseType::Mysql => Ok(vec![]),
+        }
+    }
+
+    /// The schema being browsed, falling back to the search_path's first
+    /// schema. `None` where the database has no schemas.
+    pub async fn current_schema(&self) -> Result<Option<String>, sqlx::Error> {
+        match (&self.config.database_type, &self.schema) {
+            (DatabaseType::Postgres, Some(schema)) => Ok(Some(schema.clone())),
+            (DatabaseType::Postgres, None) => {
+                sqlx::query_scalar("SELECT current_schema()::text")
+                    .fetch_one(self.pool)
+                    .await
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// The URL connected to, with any password masked.
+    pub fn display_url(&self) -> String {
+        match Url::parse(&self.config.url) {
+            Ok(mut url) if url.password().is_some() => {
+                let _ = url.set_password(Some("****"));
+                url.to_string()
+            }
+            _ => self.config.url.clone(),
+        }
+    }
+
+    /// Version of the database server, or of the SQLite library.
+    pub async fn get_server_version(&self) -> Result<String, sqlx::Error> {
+        let sql = match self.config.database_type {
+            DatabaseType::Sqlite => "SELECT sqlite_version()",
+            DatabaseType::Mysql => "SELECT CAST(VERSION() AS CHAR)",
+            DatabaseType::Postgres => "SELECT current_setting('server_version')",
+        };
+        sqlx::query_scalar(sql).fetch_one(self.pool).await
+    }
+
+    pub async fn get_index_count(&self) -> Result<i64, sqlx::Error> {
+        let sql = match self.config.database_type {
+            DatabaseType::Sqlite => {
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'index'".to_string()
+            }
+            DatabaseType::Mysql => r#"
+                SELECT COUNT(DISTINCT TABLE_NAME, INDEX_NAME)
+                FROM information_schema.statistics
+                WHERE TABLE_SCHEMA = DATABASE()
+                "#
+            .to_string(),
+            DatabaseType::Postgres => format!(
+                r#"
+                SELECT COUNT(*)
+                FROM pg_index i
+                JOIN pg_class c ON c.oid = i.indrelid
+                JOIN pg_namespace n ON n.oid = c.relnamespace
+                WHERE {}
+                "#,
+                self.pg_schema_condition("n")
+            ),
+        };
+        sqlx::query_scalar(&sql).fetch_one(self.pool).await
+    }
+
+    /// Row estimates and sizes of each table from the server's statistics,
+    /// so no table is scanned. SQLite keeps no such statistics.
+    pub async fn get_table_stats(&self) -> Result<Vec<TableStats>, sqlx::Error> {
+        let sql = match self.config.database_type {
+            DatabaseType::Sqlite => return Ok(vec![]),
+            DatabaseType::Mysql => r#"
+                SELECT
+                    CAST(TABLE_NAME AS CHAR) AS name,
+                    CAST(TABLE_ROWS AS SIGNED) AS row_estimate,
+                    CAST(DATA_LENGTH + INDEX_LENGTH AS SIGNED) AS size
+                FROM information_schema.tables
+                WHERE TABLE_SCHEMA = DATABASE() AND TABLE_TYPE = 'BASE TABLE'
+                ORDER BY TABLE_NAME
+                "#
+            .to_string(),
+            // reltuples is -1 until the table is first analyzed.
+            DatabaseType::Postgres => format!(
+                r#"
+                SELECT
+                    c.relname::text AS name,
+                    CASE WHEN c.reltuples >= 0 THEN c.reltuples::bigint END AS row_estimate,
+                    pg_total_relation_size(c.oid) AS size
+                FROM pg_class c
+                JOIN pg_namespace n ON n.oid = c.relnamespace
+                WHERE c.relkind IN ('r', 'p') AND {}
+                ORDER BY c.relname
+                "#,
+                self.pg_schema_condition("n")
+            ),
+        };
+
+        let rows = sqlx::query(&sql).fetch_all(self.pool).await?;
+        let mut stats = Vec::new();
+        for row in rows {
+            let size: Option<i64> = row.try_get("size").ok().flatten();
+            stats.push(TableStats {
+                name: row.try_get("name")?,
+                row_estimate: row.try_get("row_estimate").ok().flatten(),
+                size: size.map(|size| size as u64),
+            });
+        }
+        Ok(stats)
+    }
+
+    /// Storage settings of a SQLite database; empty for other databases.
+    pub async fn get_pragmas(&self) -> Result<Vec<(String, String)>, sqlx::Error> {
+        if !matches!(self.config.database_type, DatabaseType::Sqlite) {
+            return Ok(vec![]);
+        }
+
+        let mut pragmas = Vec::new();
+        for name in [
+            "page_size",
+            "page_count",
+            "freelist_count",
+            "journal_mode",
+            "encoding",
+        ] {
+            let row = sqlx::query(&format!("PRAGMA {}", name))
+                .fetch_one(self.pool)
+                .await?;
+            pragmas.push((name.to_string(), decode_cell(&row, 0).to_string()));
+        }
+        Ok(pragmas)
     }
 
     pub async fn get_database_info(&self) -> Result<DatabaseInfo, sqlx::Error> {
@@ -143,20 +559,45 @@ impl<'a> DatabaseManager<'a> {
     }
 
     async fn get_mysql_info(&self) -> Result<DatabaseInfo, sqlx::Error> {
+        // Dates come as Unix timestamps, which the Any driver can decode.
+        let row = sqlx::query(
+            r#"
+            SELECT
+                CAST(DATABASE() AS CHAR) AS name,
+                CAST(SUM(DATA_LENGTH + INDEX_LENGTH) AS SIGNED) AS size,
+                CAST(UNIX_TIMESTAMP(MIN(CREATE_TIME)) AS SIGNED) AS created,
+                CAST(UNIX_TIMESTAMP(MAX(UPDATE_TIME)) AS SIGNED) AS modified
+            FROM information_schema.tables
+            WHERE TABLE_SCHEMA = DATABASE()
+            "#,
+        )
+        .fetch_one(self.pool)
+        .await?;
+
+        let size: Option<i64> = row.try_get("size").ok().flatten();
+        let created: Option<i64> = row.try_get("created").ok().flatten();
+        let modified: Option<i64> = row.try_get("modified").ok().flatten();
         Ok(DatabaseInfo {
-            filename: None,
-            size: None,
-            created: None,
-            modified: None,
+            filename: row.try_get("name").ok().flatten(),
+            size: size.map(|size| size as u64),
+            created: created.and_then(|t| chrono::DateTime::from_timestamp(t, 0)),
+            modified: modified.and_then(|t| chrono::DateTime::from_timestamp(t, 0)),
             readonly: self.config.readonly,
             database_type: self.config.database_type.clone(),
         })
     }
 
     async fn get_postgres_info(&self) -> Result<DatabaseInfo, sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT current_database()::text AS name, pg_database_size(current_database()) AS size",
+        )
+        .fetch_one(self.pool)
+        .await?;
+
+        let size: i64 = row.try_get("size")?;
         Ok(DatabaseInfo {
-            filename: None,
-            size: None,
+            filename: Some(row.try_get("name")?),
+            size: Some(size as u64),
             created: None,
             modified: None,
             readonly: self.config.readonly,
@@ -181,7 +622,10 @@ impl<'a> DatabaseManager<'a> {
                 Ok(tables)
             }
             DatabaseType::Mysql => {
-                let rows = sqlx::query("SHOW TABLES").fetch_all(self.pool).await?;
+                // Views are listed separately.
+                let rows = sqlx::query("SHOW FULL TABLES WHERE Table_type = 'BASE TABLE'")
+                    .fetch_all(self.pool)
+                    .await?;
 
                 let mut tables = Vec::new();
                 for row in rows {
@@ -195,11 +639,17 @@ impl<'a> DatabaseManager<'a> {
                 Ok(tables)
             }
             DatabaseType::Postgres => {
-                let rows = sqlx::query(
-                    "SELECT tablename FROM pg_tables WHERE schemaname = 'public' ORDER BY tablename"
-                )
-                .fetch_all(self.pool)
-                .await?;
+                let sql = format!(
+                    r#"
+                    SELECT c.relname::text AS tablename
+                    FROM pg_class c
+                    JOIN pg_namespace n ON n.oid = c.relnamespace
+                    WHERE c.relkind IN ('r', 'p') AND {}
+                    ORDER BY c.relname
+                    "#,
+                    self.pg_schema_condition("n")
+                );
+                let rows = sqlx::query(&sql).fetch_all(self.pool).await?;
 
                 let mut tables = Vec::new();
                 for row in rows {
@@ -211,20 +661,176 @@ impl<'a> DatabaseManager<'a> {
         }
     }
 
-    pub async fn get_table_info(&self, table_name: &str) -> Result<TableInfo, sqlx::Error> {
-        match self.config.database_type {
-            DatabaseType::Sqlite => self.get_sqlite_table_info(table_name).await,
-            DatabaseType::Mysql => self.get_mysql_table_info(table_name).await,
-            DatabaseType::Postgres => self.get_postgres_table_info(table_name).await,
+    pub async fn get_table_info(&self, table: &QualifiedName) -> Result<TableInfo, sqlx::Error> {
+        let mut table_info = match self.config.database_type {
+            DatabaseType::Sqlite => self.get_sqlite_table_info(&table.name).await,
+            DatabaseType::Mysql => self.get_mysql_table_info(&table.name).await,
+            DatabaseType::Postgres => self.get_postgres_table_info(table).await,
+        }?;
+        table_info.schema = table.schema.clone();
+        table_info.is_view = self.is_view(table).await?;
+        Ok(table_info)
+    }
+
+    /// Whether `name` is a view (or a Postgres materialized view) rather
+    /// than a table.
+    pub async fn is_view(&self, name: &QualifiedName) -> Result<bool, sqlx::Error> {
+        let sql = match self.config.database_type {
+            DatabaseType::Sqlite => {
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'view' AND name = ?".to_string()
+            }
+            DatabaseType::Mysql => {
+                "SELECT COUNT(*) FROM information_schema.tables WHERE TABLE_SCHEMA = DATABASE() AND TABLE_TYPE = 'VIEW' AND TABLE_NAME = ?".to_string()
+            }
+            DatabaseType::Postgres => format!(
+                "SELECT COUNT(*) FROM pg_class c WHERE c.relname = $1 AND c.relkind IN ('v', 'm') AND {}",
+                self.pg_namespace_condition(name, "c")
+            ),
+        };
+        let count: i64 = sqlx::query_scalar(&sql)
+            .bind(&name.name)
+            .fetch_one(self.pool)
+            .await?;
+        Ok(count > 0)
+    }
+
+    pub async fn get_views(&self) -> Result<Vec<ViewInfo>, sqlx::Error> {
+        let sql = match self.config.database_type {
+            DatabaseType::Sqlite => {
+                "SELECT name, sql AS definition, 0 AS materialized FROM sqlite_master WHERE type = 'view' ORDER BY name".to_string()
+            }
+            DatabaseType::Mysql => r#"
+                SELECT
+                    CAST(TABLE_NAME AS CHAR) AS name,
+                    CAST(VIEW_DEFINITION AS CHAR) AS definition,
+                    0 AS materialized
+                FROM information_schema.views
+                WHERE TABLE_SCHEMA = DATABASE()
+                ORDER BY TABLE_NAME
+                "#
+            .to_string(),
+            DatabaseType::Postgres => format!(
+                r#"
+                SELECT
+                    c.relname::text AS name,
+                    pg_get_viewdef(c.oid, true) AS definition,
+                    CASE WHEN c.relkind = 'm' THEN 1 ELSE 0 END AS materialized
+                FROM pg_class c
+                JOIN pg_namespace n ON n.oid = c.relnamespace
+                WHERE c.relkind IN ('v', 'm') AND {}
+                ORDER BY c.relname
+                "#,
+                self.pg_schema_condition("n")
+            ),
+        };
+        let rows = sqlx::query(&sql).fetch_all(self.pool).await?;
+
+        let mut views = Vec::new();
+        for row in rows {
+            let definition: Option<String> = row.try_get("definition").ok().flatten();
+            let definition = definition.unwrap_or_default();
+            let materialized: i32 = row.try_get("materialized")?;
+            views.push(ViewInfo {
+                name: row.try_get("name")?,
+                // SQLite keeps the whole CREATE VIEW statement.
+                definition: match self.config.database_type {
+                    DatabaseType::Sqlite => statement::view_query(&definition).to_string(),
+                    _ => statement::strip_terminator(definition.trim()).to_string(),
+                },
+                materialized: materialized != 0,
+            });
+        }
+        Ok(views)
+    }
+
+    /// Create a view, or replace it. Materialized views, and any view on
+    /// SQLite, can't be replaced in place, so they are dropped and created
+    /// again in one transaction.
+    pub async fn create_view(&self, request: &CreateViewRequest) -> Result<(), sqlx::Error> {
+        let db_type = &self.config.database_type;
+        let name = db_type.quote_qualified(&request.name);
+        let query = statement::strip_terminator(request.query.trim());
+        let materialized = request.materialized && matches!(db_type, DatabaseType::Postgres);
+        let kind = if materialized {
+            "MATERIALIZED VIEW"
+        } else {
+            "VIEW"
+        };
+
+        let mut statements = Vec::new();
+        let replace_in_place = !materialized && !matches!(db_type, DatabaseType::Sqlite);
+        if request.replace && !replace_in_place {
+            statements.push(format!("DROP {} IF EXISTS {}", kind, name));
+        }
+        statements.push(format!(
+            "CREATE {}{} {} AS {}",
+            if request.replace && replace_in_place {
+                "OR REPLACE "
+            } else {
+                ""
+            },
+            kind,
+            name,
+            query
+        ));
+
+        // The query's own table names resolve in the schema being browsed.
+        let mut conn = self.acquire().await?;
+        let mut tx = conn.begin().await?;
+        for sql in &statements {
+            sqlx::query(sql).execute(&mut *tx).await?;
         }
+        tx.commit().await
+    }
+
+    pub async fn drop_view(
+        &self,
+        name: &QualifiedName,
+        materialized: bool,
+    ) -> Result<(), sqlx::Error> {
+        let sql = format!(
+            "DROP {} {}",
+            if materialized {
+                "MATERIALIZED VIEW"
+            } else {
+                "VIEW"
+            },
+            self.config.database_type.quote_qualified(name)
+        );
+        sqlx::query(&sql).execute(self.pool).await?;
+        Ok(())
+    }
+
+    pub async fn refresh_materialized_view(&self, name: &QualifiedName) -> Result<(), sqlx::Error> {
+        let sql = format!(
+            "REFRESH MATERIALIZED VIEW {}",
+            self.config.database_type.quote_qualified(name)
+        );
+        sqlx::query(&sql).execute(self.pool).await?;
+        Ok(())
     }
 
     async fn get_sqlite_table_info(&self, table_name: &str) -> Result<TableInfo, sqlx::Error> {
-        let rows = sqlx::query("PRAGMA table_info(?)")
+        // PRAGMA arguments cannot be bound, but the table-valued form accepts parameters.
+        // table_xinfo also lists generated columns; hidden 1 marks the hidden
+        // columns of virtual tables.
+        let rows = sqlx::query("SELECT * FROM pragma_table_xinfo(?) WHERE hidden <> 1")
             .bind(table_name)
             .fetch_all(self.pool)
             .await?;
 
+        // Collations and generation expressions are only kept in the table's
+        // definition. Views have none.
+        let create_sql = self
+            .get_create_table_sql(&QualifiedName::new(None, table_name))
+            .await
+            .unwrap_or_default();
+        let (definitions, options) = statement::create_table_body(&create_sql)
+            .map(statement::table_definitions)
+            .unwrap_or_default();
+        let without_rowid = options.to_ascii_uppercase().contains("WITHOUT ROWID");
+
+        let mut primary_keys = 0;
         let mut columns = Vec::new();
         for row in rows {
             let name: String = row.try_get("name")?;
@@ -232,35 +838,113 @@ impl<'a> DatabaseManager<'a> {
             let notnull: i32 = row.try_get("notnull")?;
             let dflt_value: Option<String> = row.try_get("dflt_value").ok();
             let pk: i32 = row.try_get("pk")?;
+            if pk != 0 {
+                primary_keys += 1;
+            }
+
+            let clauses = definitions
+                .iter()
+                .find(|d| statement::column_name(d).is_some_and(|n| n == name))
+                .map(|d| statement::column_clauses(d).1)
+                .unwrap_or_default();
+            let clause = |keyword: &str| {
+                clauses
+                    .iter()
+                    .find(|c| statement::leading_keyword(c) == keyword)
+            };
+            let collation = clause("COLLATE")
+                .and_then(|c| c.split_whitespace().nth(1))
+                .map(|c| {
+                    c.trim_matches(|q| q == '"' || q == '\'' || q == '`')
+                        .to_string()
+                });
+            let generated = clause("GENERATED")
+                .or_else(|| clause("AS"))
+                .and_then(|c| Some(c[c.find('(')?..=c.rfind(')')?].to_string()));
+            let autoincrement = clauses
+                .iter()
+                .any(|c| c.to_ascii_uppercase().contains("AUTOINCREMENT"));
 
+            let (max_length, numeric_precision, numeric_scale) = type_sizes(&data_type);
             columns.push(ColumnInfo {
                 name,
-                data_type,
                 nullable: notnull == 0,
                 default_value: dflt_value,
                 is_primary_key: pk != 0,
+                is_auto_increment: autoincrement
+                    || (pk != 0 && data_type.eq_ignore_ascii_case("INTEGER") && !without_rowid),
+                data_type,
+                max_length,
+                numeric_precision,
+                numeric_scale,
+                collation,
+                generated,
+                comment: None,
             });
         }
 
+        // Only a single-column INTEGER PRIMARY KEY aliases the rowid.
+        if primary_keys > 1 {
+            for column in &mut columns {
+                column.is_auto_increment = false;
+            }
+        }
+
         Ok(TableInfo {
             name: table_name.to_string(),
+            schema: None,
             columns,
+            is_view: false,
         })
     }
 
     async fn get_mysql_table_info(&self, table_name: &str) -> Result<TableInfo, sqlx::Error> {
-        let rows = sqlx::query("DESCRIBE ?")
-            .bind(table_name)
-            .fetch_all(self.pool)
-            .await?;
+        // Identifiers can't be bound, so DESCRIBE is no use here. Text
+        // columns are cast, as information_schema may return them as binary.
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                CAST(COLUMN_NAME AS CHAR) AS column_name,
+                CAST(COLUMN_TYPE AS CHAR) AS column_type,
+                CAST(IS_NULLABLE AS CHAR) AS is_nullable,
+                CAST(COLUMN_DEFAULT AS CHAR) AS column_default,
+                CAST(COLUMN_KEY AS CHAR) AS column_key,
+                CAST(EXTRA AS CHAR) AS extra,
+                CAST(CHARACTER_MAXIMUM_LENGTH AS SIGNED) AS max_length,
+                CASE WHEN DATA_TYPE IN ('decimal', 'numeric')
+                    THEN CAST(NUMERIC_PRECISION AS SIGNED) END AS numeric_precision,
+                CASE WHEN DATA_TYPE IN ('decimal', 'numeric')
+                    THEN CAST(NUMERIC_SCALE AS SIGNED) END AS numeric_scale,
+                CAST(COLLATION_NAME AS CHAR) AS collation_name,
+                CAST(GENERATION_EXPRESSION AS CHAR) AS generation_expression,
+                CAST(COLUMN_COMMENT AS CHAR) AS column_comment
+            FROM information_schema.columns
+            WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ?
+            ORDER BY ORDINAL_POSITION
+            "#,
+        )
+        .bind(table_name)
+        .fetch_all(self.pool)
+        .await?;
 
         let mut columns = Vec::new();
         for row in rows {
-            let name: String = row.try_get("Field")?;
-            let data_type: String = row.try_get("Type")?;
-            let nullable: String = row.try_get("Null")?;
-            let default_value: Option<String> = row.try_get("Default").ok();
-            let key: String = row.try_get("Key")?;
+            let name: String = row.try_get("column_name")?;
+            let data_type: String = row.try_get("column_type")?;
+            let nullable: String = row.try_get("is_nullable")?;
+            let default_value: Option<String> = row.try_get("column_default").ok().flatten();
+            let key: String = row.try_get("column_key")?;
+            let extra: String = row.try_get("extra").unwrap_or_default();
+            let generated: Option<String> = row
+                .try_get::<Option<String>, _>("generation_expression")
+                .ok()
+                .flatten()
+                .filter(|e| !e.is_empty());
+            let comment: Option<String> = row
+                .try_get::<Option<String>, _>("column_comment")
+                .ok()
+                .flatten()
+                .filter(|c| !c.is_empty());
 
             columns.push(ColumnInfo {
                 name,
@@ -268,40 +952,76 @@ impl<'a> DatabaseManager<'a> {
                 nullable: nullable.to_uppercase() == "YES",
                 default_value,
                 is_primary_key: key == "PRI",
+                is_auto_increment: extra.to_lowercase().contains("auto_increment"),
+                max_length: row.try_get("max_length").ok().flatten(),
+                numeric_precision: row.try_get("numeric_precision").ok().flatten(),
+                numeric_scale: row.try_get("numeric_scale").ok().flatten(),
+                collation: row.try_get("collation_name").ok().flatten(),
+                generated,
+                comment,
             });
         }
 
         Ok(TableInfo {
             name: table_name.to_string(),
+            schema: None,
             columns,
+            is_view: false,
         })
     }
 
-    async fn get_postgres_table_info(&self, table_name: &str) -> Result<TableInfo, sqlx::Error> {
-        let rows = sqlx::query(
+    async fn get_postgres_table_info(
+        &self,
+        table: &QualifiedName,
+    ) -> Result<TableInfo, sqlx::Error> {
+        // Catalog names and booleans are cast, as the Any driver can't decode them.
+        // The catalog is read directly since information_schema leaves out
+        // materialized views; format_type names types the way
+        // information_schema does, with enums and arrays under their real name
+        // and modifiers such as a length or precision included.
+        let sql = format!(
             r#"
             SELECT
-                column_name,
-                data_type,
-                is_nullable,
-                column_default,
-                CASE WHEN pk.column_name IS NOT NULL THEN true ELSE false END as is_primary_key
-            FROM information_schema.columns c
-            LEFT JOIN (
-                SELECT ku.column_name
-                FROM information_schema.table_constraints tc
-                JOIN information_schema.key_column_usage ku
-                    ON tc.constraint_name = ku.constraint_name
-                WHERE tc.constraint_type = 'PRIMARY KEY'
-                    AND tc.table_name = $1
-            ) pk ON c.column_name = pk.column_name
-            WHERE c.table_name = $1
-            ORDER BY c.ordinal_position
+                a.attname::text AS column_name,
+                format_type(a.atttypid, a.atttypmod) AS data_type,
+                CASE WHEN a.attnotnull THEN 'NO' ELSE 'YES' END AS is_nullable,
+                CASE WHEN a.attgenerated = '' THEN pg_get_expr(d.adbin, d.adrelid) END
+                    AS column_default,
+                CASE WHEN a.attgenerated <> '' THEN pg_get_expr(d.adbin, d.adrelid) END
+                    AS generation_expression,
+                CASE WHEN EXISTS (
+                    SELECT 1 FROM pg_index i
+                    WHERE i.indrelid = a.attrelid AND i.indisprimary AND a.attnum = ANY(i.indkey)
+                ) THEN 1 ELSE 0 END AS is_primary_key,
+                CASE WHEN a.attidentity <> ''
+                    OR pg_get_expr(d.adbin, d.adrelid) LIKE 'nextval(%'
+                THEN 1 ELSE 0 END AS is_auto_increment,
+                information_schema._pg_char_max_length(a.atttypid, a.atttypmod)::bigint
+                    AS max_length,
+                CASE WHEN a.atttypid = 'numeric'::regtype THEN
+                    information_schema._pg_numeric_precision(a.atttypid, a.atttypmod)::bigint
+                END AS numeric_precision,
+                CASE WHEN a.atttypid = 'numeric'::regtype THEN
+                    information_schema._pg_numeric_scale(a.atttypid, a.atttypmod)::bigint
+                END AS numeric_scale,
+                (
+                    SELECT co.collname::text FROM pg_collation co
+                    WHERE co.oid = a.attcollation AND a.attcollation <> t.typcollation
+                ) AS collation_name,
+                col_description(a.attrelid, a.attnum) AS column_comment
+            FROM pg_attribute a
+            JOIN pg_type t ON t.oid = a.atttypid
+            LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+            WHERE a.attrelid = (SELECT c.oid FROM pg_class c WHERE c.relname = $1 AND {})
+                AND a.attnum > 0 AND NOT a.attisdropped
+            ORDER BY a.attnum
             "#,
-        )
-        .bind(table_name)
-        .fetch_all(self.pool)
-        .await?;
+            self.pg_namespace_condition(table, "c")
+        );
+        let rows = sqlx::query(&sql)
+            .bind(&table.name)
+            .fetch_all(self.pool)
+            .await?;
 
         let mut columns = Vec::new();
         for row in rows {
@@ -309,112 +1029,2332 @@ impl<'a> DatabaseManager<'a> {
             let data_type: String = row.try_get("data_type")?;
             let is_nullable: String = row.try_get("is_nullable")?;
             let default_value: Option<String> = row.try_get("column_default").ok();
-            let is_primary_key: bool = row.try_get("is_primary_key")?;
+            let is_primary_key: i32 = row.try_get("is_primary_key")?;
+            let is_auto_increment: i32 = row.try_get("is_auto_increment")?;
 
             columns.push(ColumnInfo {
                 name,
                 data_type,
                 nullable: is_nullable.to_uppercase() == "YES",
                 default_value,
-                is_primary_key,
+                is_primary_key: is_primary_key != 0,
+                is_auto_increment: is_auto_increment != 0,
+                max_length: row.try_get("max_length").ok().flatten(),
+                numeric_precision: row.try_get("numeric_precision").ok().flatten(),
+                numeric_scale: row.try_get("numeric_scale").ok().flatten(),
+                collation: row.try_get("collation_name").ok().flatten(),
+                generated: row.try_get("generation_expression").ok().flatten(),
+                comment: row.try_get("column_comment").ok().flatten(),
             });
         }
 
         Ok(TableInfo {
-            name: table_name.to_string(),
+            name: table.name.clone(),
+            schema: None,
             columns,
+            is_view: false,
         })
     }
 
+    /// Acquire a connection for running user SQL, with unqualified names
+    /// resolving in the schema being browsed. A connection pointed at another
+    /// schema is taken out of the pool and closed after use, so the
+    /// search_path never carries over to other requests.
+    async fn acquire(&self) -> Result<SchemaConnection, sqlx::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let Some(schema) = &self.schema else {
+            return Ok(SchemaConnection::Pooled(conn));
+        };
+        let sql = format!(
+            "SET search_path TO {}, public",
+            self.config.database_type.quote_identifier(schema)
+        );
+        sqlx::query(&sql).execute(&mut *conn).await?;
+        Ok(SchemaConnection::Detached(conn.detach()))
+    }
+
     pub async fn execute_query(&self, sql: &str) -> Result<QueryResult, sqlx::Error> {
-        let rows = sqlx::query(sql).fetch_all(self.pool).await?;
+        let mut conn = self.acquire().await?;
+        let rows = sqlx::query(sql).fetch_all(&mut *conn).await?;
+        Ok(query_result(rows))
+    }
 
-        let mut result_rows = Vec::new();
-        let mut columns = Vec::new();
+    /// Run a statement without collecting rows, returning the number of rows
+    /// affected and the id of the last inserted row where the driver knows it.
+    pub async fn execute_statement(&self, sql: &str) -> Result<QueryResult, sqlx::Error> {
+        let mut conn = self.acquire().await?;
+        self.execute_on(&mut conn, sql).await
+    }
 
-        if let Some(first_row) = rows.first() {
-            columns = first_row
-                .columns()
-                .iter()
-                .map(|col| col.name().to_string())
-                .collect();
-        }
+    async fn execute_on(
+        &self,
+        conn: &mut AnyConnection,
+        sql: &str,
+    ) -> Result<QueryResult, sqlx::Error> {
+        let result = sqlx::query(sql).execute(&mut *conn).await?;
 
-        for row in rows {
-            let mut row_data = Vec::new();
-            for (i, _column) in row.columns().iter().enumerate() {
-                let value: Option<String> = row.try_get(i).ok();
-                row_data.push(value);
-            }
-            result_rows.push(row_data);
-        }
+        let last_insert_id = match statement::leading_keyword(sql).as_str() {
+            "INSERT" | "REPLACE" => match self.config.database_type {
+                // The Any driver drops SQLite's rowid, so ask the same connection.
+                DatabaseType::Sqlite => sqlx::query_scalar::<_, i64>("SELECT last_insert_rowid()")
+                    .fetch_one(&mut *conn)
+                    .await
+                    .ok(),
+                _ => result.last_insert_id(),
+            },
+            _ => None,
+        };
 
         Ok(QueryResult {
-            columns,
-            rows: result_rows,
-            rows_affected: None,
+            columns: vec![],
+            rows: vec![],
+            rows_affected: Some(result.rows_affected()),
+            last_insert_id,
         })
     }
 
-    pub async fn get_table_row_count(&self, table_name: &str) -> Result<i64, sqlx::Error> {
-        let sql = format!("SELECT COUNT(*) as count FROM \"{}\"", table_name);
-        let row = sqlx::query(&sql).fetch_one(self.pool).await?;
-        let count: i64 = row.try_get("count")?;
-        Ok(count)
+    /// Run any statement: those returning rows are fetched, the rest executed
+    /// so the affected row count is reported.
+    pub async fn run_statement(&self, sql: &str) -> Result<QueryResult, sqlx::Error> {
+        let mut conn = self.acquire().await?;
+        self.run_on(&mut conn, sql).await
     }
 
-    pub async fn get_indexes(&self, table_name: &str) -> Result<Vec<IndexInfo>, sqlx::Error> {
-        match self.config.database_type {
-            DatabaseType::Sqlite => {
-                let rows = sqlx::query("PRAGMA index_list(?)")
-                    .bind(table_name)
-                    .fetch_all(self.pool)
-                    .await?;
+    async fn run_on(
+        &self,
+        conn: &mut AnyConnection,
+        sql: &str,
+    ) -> Result<QueryResult, sqlx::Error> {
+        match statement::classify(sql) {
+            StatementKind::Execute => self.execute_on(conn, sql).await,
+            StatementKind::Query | StatementKind::Rows => {
+                let rows = sqlx::query(sql).fetch_all(&mut *conn).await?;
+                Ok(query_result(rows))
+            }
+        }
+    }
 
-                let mut indexes = Vec::new();
-                for row in rows {
-                    let name: String = row.try_get("name")?;
-                    let unique: i32 = row.try_get("unique")?;
+    /// Run a script's statements in order on one connection, stopping at the
+    /// first failure. With `use_transaction` the script runs in a single
+    /// transaction that a failure rolls back.
+    pub async fn run_script(
+        &self,
+        statements: &[String],
+        use_transaction: bool,
+    ) -> Result<Vec<StatementResult>, sqlx::Error> {
+        let mut conn = self.acquire().await?;
+        if !use_transaction {
+            return Ok(self.run_statements(&mut conn, statements).await);
+        }
 
-                    indexes.push(IndexInfo {
-                        name,
-                        unique: unique != 0,
-                        columns: vec![], // Would need additional query to get columns
-                    });
+        let mut tx = conn.begin().await?;
+        let results = self.run_statements(&mut tx, statements).await;
+        if results.iter().any(|r| r.result.is_err()) {
+            tx.rollback().await?;
+        } else {
+            tx.commit().await?;
+        }
+        Ok(results)
+    }
+
+    async fn run_statements(
+        &self,
+        conn: &mut AnyConnection,
+        statements: &[String],
+    ) -> Vec<StatementResult> {
+        let mut results = Vec::new();
+        for sql in statements {
+            let started = Instant::now();
+            let result = async {
+                // An earlier statement may have changed session settings.
+                if self.config.readonly {
+                    enforce_readonly(conn, &self.config.database_type).await?;
                 }
-                Ok(indexes)
+                self.run_on(conn, sql).await
+            }
+            .await;
+            let failed = result.is_err();
+            results.push(StatementResult {
+                sql: sql.clone(),
+                result,
+                elapsed: started.elapsed(),
+            });
+            if failed {
+                break;
             }
-            _ => Ok(vec![]), // Placeholder for MySQL and PostgreSQL
         }
+        results
     }
-}
 
-#[derive(Debug, Clone)]
-#[allow(unused)]
-pub struct TableInfo {
-    pub name: String,
-    pub columns: Vec<ColumnInfo>,
-}
+    /// Fetch one page of a query's rows, optionally ordered by the 1-based
+    /// result column `ordering` (negative for descending). Also reports
+    /// whether more rows follow the page.
+    pub async fn fetch_page(
+        &self,
+        sql: &str,
+        page: usize,
+        per_page: usize,
+        ordering: Option<i32>,
+    ) -> Result<(QueryResult, bool), sqlx::Error> {
+        let order_by = match ordering {
+            Some(column) if column != 0 => format!(
+                " ORDER BY {} {}",
+                column.unsigned_abs(),
+                if column < 0 { "DESC" } else { "ASC" }
+            ),
+            _ => String::new(),
+        };
 
-#[derive(Debug, Clone)]
-pub struct ColumnInfo {
-    pub name: String,
-    pub data_type: String,
-    pub nullable: bool,
-    pub default_value: Option<String>,
-    pub is_primary_key: bool,
-}
+        let offset = page_offset(page, per_page)
+            .ok_or_else(|| sqlx::Error::Protocol("page out of range".into()))?;
 
-#[derive(Debug, Clone)]
-pub struct IndexInfo {
-    pub name: String,
-    pub unique: bool,
-    pub columns: Vec<String>,
-}
+        // One extra row tells whether another page exists.
+        let page_sql = format!(
+            "SELECT * FROM (\n{}\n) AS _page{} LIMIT {} OFFSET {}",
+            statement::strip_terminator(sql),
+            order_by,
+            per_page + 1,
+            offset
+        );
 
-#[derive(Debug, Clone)]
-pub struct QueryResult {
-    pub columns: Vec<String>,
-    pub rows: Vec<Vec<Option<String>>>,
-    pub rows_affected: Option<u64>,
+        let mut result = self.execute_query(&page_sql).await?;
+        let has_more = result.rows.len() > per_page;
+        result.rows.truncate(per_page);
+        Ok((result, has_more))
+    }
+
+    /// Count a query's rows, stopping at `limit` + 1 so huge results stay
+    /// cheap. A result above `limit` means the count was capped.
+    pub async fn count_rows(&self, sql: &str, limit: i64) -> Result<i64, sqlx::Error> {
+        let count_sql = format!(
+            "SELECT COUNT(*) AS count FROM (SELECT 1 AS one FROM (\n{}\n) AS _rows LIMIT {}) AS _count",
+            statement::strip_terminator(sql),
+            limit + 1
+        );
+        let mut conn = self.acquire().await?;
+        let row = sqlx::query(&count_sql).fetch_one(&mut *conn).await?;
+        match decode_cell(&row, 0) {
+            CellValue::Int(count) => Ok(count),
+            _ => Err(sqlx::Error::ColumnNotFound("count".to_string())),
+        }
+    }
+
+    /// Run a built statement and collect the rows it returns.
+    pub async fn fetch(&self, builder: &QueryBuilder) -> Result<QueryResult, sqlx::Error> {
+        let rows = builder.build().fetch_all(self.pool).await?;
+        Ok(query_result(rows))
+    }
+
+    /// Run a built `SELECT COUNT(*)` statement.
+    pub async fn fetch_count(&self, builder: &QueryBuilder) -> Result<i64, sqlx::Error> {
+        let row = builder.build().fetch_one(self.pool).await?;
+        match decode_cell(&row, 0) {
+            CellValue::Int(count) => Ok(count),
+            _ => Err(sqlx::Error::ColumnNotFound("count".to_string())),
+        }
+    }
+
+    /// Run a built statement, returning the number of rows affected.
+    pub async fn execute(&self, builder: &QueryBuilder) -> Result<u64, sqlx::Error> {
+        Ok(builder.build().execute(self.pool).await?.rows_affected())
+    }
+
+    /// Decide how single rows of `table_info` are addressed: by primary key,
+    /// else a unique index over NOT NULL columns, else the backend's physical
+    /// row id, and on MySQL, which has none, by matching the whole row.
+    pub async fn row_identity(&self, table_info: &TableInfo) -> Result<RowIdentity, sqlx::Error> {
+        let primary_key: Vec<String> = table_info
+            .columns
+            .iter()
+            .filter(|c| c.is_primary_key)
+            .map(|c| c.name.clone())
+            .collect();
+        if !primary_key.is_empty() {
+            return Ok(RowIdentity::PrimaryKey(primary_key));
+        }
+        // Views have no indexes or physical row id to fall back on.
+        if table_info.is_view {
+            return Ok(RowIdentity::Unsupported);
+        }
+
+        let not_null = |name: &str| {
+            table_info
+                .columns
+                .iter()
+                .any(|c| c.name == name && !c.nullable)
+        };
+        let unique_key = self
+            .get_indexes(&table_info.qualified_name())
+            .await?
+            .into_iter()
+            .filter(|index| index.unique && index.predicate.is_none())
+            .filter_map(|index| {
+                index
+                    .columns
+                    .into_iter()
+                    .map(|column| column.name.filter(|name| not_null(name)))
+                    .collect::<Option<Vec<String>>>()
+            })
+            .min_by_key(|columns| columns.len());
+        if let Some(columns) = unique_key {
+            return Ok(RowIdentity::UniqueKey(columns));
+        }
+
+        if self.config.database_type.row_id_column().is_some() {
+            return Ok(RowIdentity::RowId);
+        }
+
+        // Floats, JSON and blobs don't compare reliably against their text form.
+        let comparable: Vec<String> = table_info
+            .columns
+            .iter()
+            .filter(|c| {
+                let data_type = c.data_type.to_lowercase();
+                !matches!(c.input_kind(), "blob" | "json")
+                    && !["float", "double", "real"]
+                        .iter()
+                        .any(|t| data_type.starts_with(t))
+            })
+            .map(|c| c.name.clone())
+            .collect();
+        if comparable.is_empty() {
+            Ok(RowIdentity::Unsupported)
+        } else {
+            Ok(RowIdentity::FullRow(comparable))
+        }
+    }
+
+    /// Delete the rows matching each request's key conditions in one
+    /// transaction, returning the number of rows removed.
+    pub async fn delete_rows(
+        &self,
+        table_info: &TableInfo,
+        requests: &[DeleteRequest],
+    ) -> Result<u64, sqlx::Error> {
+        let db_type = &self.config.database_type;
+
+        let mut tx = self.pool.begin().await?;
+        let mut deleted = 0;
+
+        for request in requests {
+            // Without conditions every row would go.
+            if request.where_clause.is_empty() {
+                return Err(sqlx::Error::ColumnNotFound("row key".to_string()));
+            }
+
+            let mut builder = query_builder::delete(db_type, table_info, &request.where_clause);
+            // A full-row match may cover identical rows; only one is meant.
+            if matches!(db_type, DatabaseType::Mysql) {
+                builder.push(" LIMIT 1");
+            }
+            deleted += builder.build().execute(&mut *tx).await?.rows_affected();
+        }
+
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    /// Apply `request` to a single row, provided the row still has the
+    /// `version` the edit form was loaded with. The row is read again and
+    /// updated in one transaction, locked on backends that support it.
+    pub async fn update_row(
+        &self,
+        table_info: &TableInfo,
+        request: &UpdateRequest,
+        version: &str,
+    ) -> Result<RowUpdate, sqlx::Error> {
+        let db_type = &self.config.database_type;
+        if request.where_clause.is_empty() {
+            return Err(sqlx::Error::ColumnNotFound("row key".to_string()));
+        }
+        let mut select = query_builder::select_where(db_type, table_info, &request.where_clause);
+        select.push(" LIMIT 1");
+        if !matches!(db_type, DatabaseType::Sqlite) {
+            select.push(" FOR UPDATE");
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let rows = select.build().fetch_all(&mut *tx).await?;
+        let current = query_result(rows).with_column_types(table_info);
+        let Some(row) = current.rows.into_iter().next() else {
+            return Ok(RowUpdate::Missing);
+        };
+        if row_version(&row) != version {
+            return Ok(RowUpdate::Conflict(
+                current.columns.into_iter().zip(row).collect(),
+            ));
+        }
+
+        let mut builder = query_builder::update(db_type, table_info, request);
+        // A full-row match may cover identical rows; only one is meant.
+        if matches!(db_type, DatabaseType::Mysql) {
+            builder.push(" LIMIT 1");
+        }
+        if builder.build().execute(&mut *tx).await?.rows_affected() == 0 {
+            return Ok(RowUpdate::Missing);
+        }
+        tx.commit().await?;
+        Ok(RowUpdate::Updated)
+    }
+
+    pub async fn get_table_row_count(&self, table: &QualifiedName) -> Result<i64, sqlx::Error> {
+        let sql = format!(
+            "SELECT COUNT(*) as count FROM {}",
+            self.config.database_type.quote_qualified(table)
+        );
+        let row = sqlx::query(&sql).fetch_one(self.pool).await?;
+        let count: i64 = row.try_get("count")?;
+        Ok(count)
+    }
+
+    pub async fn add_column(&self, request: &AddColumnRequest) -> Result<(), sqlx::Error> {
+        sqlx::query(&self.add_column_sql(request))
+            .execute(self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// ALTER TABLE statement adding the column of `request`.
+    pub fn add_column_sql(&self, request: &AddColumnRequest) -> String {
+        let db_type = &self.config.database_type;
+        let column = &request.column;
+
+        let mut sql = format!(
+            "ALTER TABLE {} ADD COLUMN {}",
+            db_type.quote_qualified(&request.table),
+            db_type.column_definition(column)
+        );
+
+        if column.primary_key {
+            // Note: Adding primary key constraint to existing table is complex
+            // This is a simplified version that may not work for all databases
+            sql += " PRIMARY KEY";
+        }
+
+        sql
+    }
+
+    /// CREATE TABLE statement for `request`. Primary keys are declared as a
+    /// table constraint so they can span several columns.
+    pub fn create_table_sql(&self, request: &CreateTableRequest) -> String {
+        let db_type = &self.config.database_type;
+        let mut definitions: Vec<String> = request
+            .columns
+            .iter()
+            .map(|column| db_type.column_definition(column))
+            .collect();
+
+        let inline_key = matches!(db_type, DatabaseType::Sqlite)
+            && request.columns.iter().any(|c| c.auto_increment);
+        let primary_key: Vec<String> = request
+            .columns
+            .iter()
+            .filter(|c| c.primary_key)
+            .map(|c| db_type.quote_identifier(&c.name))
+            .collect();
+        if !primary_key.is_empty() && !inline_key {
+            definitions.push(format!("PRIMARY KEY ({})", primary_key.join(", ")));
+        }
+
+        format!(
+            "CREATE TABLE {} (\n    {}\n)",
+            db_type.quote_qualified(&request.name),
+            definitions.join(",\n    ")
+        )
+    }
+
+    pub async fn create_table(&self, request: &CreateTableRequest) -> Result<(), sqlx::Error> {
+        sqlx::query(&self.create_table_sql(request))
+            .execute(self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Rename `table`, keeping it in its schema.
+    pub async fn rename_table(
+        &self,
+        table: &QualifiedName,
+        new_name: &str,
+    ) -> Result<(), sqlx::Error> {
+        let db_type = &self.config.database_type;
+        let sql = format!(
+            "ALTER TABLE {} RENAME TO {}",
+            db_type.quote_qualified(table),
+            db_type.quote_identifier(new_name)
+        );
+        sqlx::query(&sql).execute(self.pool).await?;
+        Ok(())
+    }
+
+    pub async fn drop_table(&self, table: &QualifiedName) -> Result<(), sqlx::Error> {
+        let sql = format!(
+            "DROP TABLE {}",
+            self.config.database_type.quote_qualified(table)
+        );
+        sqlx::query(&sql).execute(self.pool).await?;
+        Ok(())
+    }
+
+    /// Remove every row of `table`. SQLite has no TRUNCATE; its DELETE
+    /// without a WHERE clause is optimized the same way.
+    pub async fn truncate_table(&self, table: &QualifiedName) -> Result<(), sqlx::Error> {
+        let db_type = &self.config.database_type;
+        let sql = match db_type {
+            DatabaseType::Sqlite => format!("DELETE FROM {}", db_type.quote_qualified(table)),
+            DatabaseType::Mysql | DatabaseType::Postgres => {
+                format!("TRUNCATE TABLE {}", db_type.quote_qualified(table))
+            }
+        };
+        sqlx::query(&sql).execute(self.pool).await?;
+        Ok(())
+    }
+
+    /// Create `new_name` in the same schema with the structure of `table`,
+    /// copying its rows too when `with_data` is set.
+    pub async fn duplicate_table(
+        &self,
+        table: &QualifiedName,
+        new_name: &str,
+        with_data: bool,
+    ) -> Result<(), sqlx::Error> {
+        let db_type = &self.config.database_type;
+        let source = db_type.quote_qualified(table);
+        // The copy goes next to the original, along with any sequences.
+        let schema = match &table.schema {
+            Some(schema) => Some(schema.clone()),
+            None => self.current_schema().await?,
+        };
+        let target = db_type.quote_qualified(&QualifiedName::new(schema.clone(), new_name));
+        let table_info = self.get_table_info(table).await?;
+
+        let create = match db_type {
+            // Reuse the original definition, constraints included, under the
+            // new name.
+            DatabaseType::Sqlite => {
+                let sql = self.get_create_table_sql(table).await?;
+                let body = statement::create_table_body(&sql)
+                    .ok_or_else(|| sqlx::Error::Protocol("unreadable table definition".into()))?;
+                format!("CREATE TABLE {} {}", target, body)
+            }
+            DatabaseType::Mysql => format!("CREATE TABLE {} LIKE {}", target, source),
+            DatabaseType::Postgres => {
+                format!("CREATE TABLE {} (LIKE {} INCLUDING ALL)", target, source)
+            }
+        };
+        let mut statements = vec![create];
+
+        match db_type {
+            // SQLite keeps indexes apart from the table definition, so they
+            // are recreated on the copy under names of their own.
+            DatabaseType::Sqlite => {
+                let mut taken: Vec<String> = sqlx::query_scalar("SELECT name FROM sqlite_master")
+                    .fetch_all(self.pool)
+                    .await?;
+                let indexes: Vec<(String, String, i32)> = sqlx::query_as(
+                    r#"
+                    SELECT m.name, m.sql, l."unique"
+                    FROM sqlite_master m
+                    JOIN pragma_index_list(m.tbl_name) l ON l.name = m.name
+                    WHERE m.type = 'index' AND m.tbl_name = ? AND m.sql IS NOT NULL
+                    "#,
+                )
+                .bind(&table.name)
+                .fetch_all(self.pool)
+                .await?;
+                // Rebuilt from its terms, so names in the statement don't matter.
+                for (name, sql, unique) in indexes {
+                    let (terms, predicate) = statement::index_definition(&sql);
+                    let index_name =
+                        free_name(&copied_index_name(&name, &table.name, new_name), &taken);
+                    let mut create = format!(
+                        "CREATE {}INDEX {} ON {} ({})",
+                        if unique != 0 { "UNIQUE " } else { "" },
+                        db_type.quote_identifier(&index_name),
+                        db_type.quote_identifier(new_name),
+                        terms.join(", ")
+                    );
+                    if let Some(predicate) = predicate {
+                        create += &format!(" WHERE {}", predicate);
+                    }
+                    statements.push(create);
+                    taken.push(index_name);
+                }
+            }
+            // LIKE copies serial defaults as they are, drawing from the
+            // original's sequences, so the copy gets sequences of its own.
+            DatabaseType::Postgres => {
+                let mut taken: Vec<String> = sqlx::query_scalar(
+                    "SELECT c.relname::text FROM pg_class c \
+                     JOIN pg_namespace n ON n.oid = c.relnamespace WHERE n.nspname = $1",
+                )
+                .bind(&schema)
+                .fetch_all(self.pool)
+                .await?;
+                for column in &table_info.columns {
+                    if !column
+                        .default_value
+                        .as_deref()
+                        .is_some_and(|d| d.starts_with("nextval("))
+                    {
+                        continue;
+                    }
+                    let sequence_name =
+                        free_name(&format!("{}_{}_seq", new_name, column.name), &taken);
+                    let sequence = db_type
+                        .quote_qualified(&QualifiedName::new(schema.clone(), &sequence_name));
+                    taken.push(sequence_name);
+                    let name = db_type.quote_identifier(&column.name);
+                    statements.push(format!(
+                        "CREATE SEQUENCE {} OWNED BY {}.{}",
+                        sequence, target, name
+                    ));
+                    statements.push(format!(
+                        "ALTER TABLE {} ALTER COLUMN {} SET DEFAULT nextval({}::regclass)",
+                        target,
+                        name,
+                        db_type.quote_literal(&sequence)
+                    ));
+                }
+            }
+            DatabaseType::Mysql => {}
+        }
+
+        if with_data {
+            // Generated columns are computed, not copied, and identity values
+            // are copied over, not generated afresh.
+            let columns = table_info
+                .columns
+                .iter()
+                .filter(|c| c.generated.is_none())
+                .map(|c| db_type.quote_identifier(&c.name))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let overriding = match db_type {
+                DatabaseType::Postgres => " OVERRIDING SYSTEM VALUE",
+                DatabaseType::Sqlite | DatabaseType::Mysql => "",
+            };
+            statements.push(format!(
+                "INSERT INTO {} ({}){} SELECT {} FROM {}",
+                target, columns, overriding, columns, source
+            ));
+
+            // Postgres sequences don't follow copied values, so they are
+            // moved past them.
+            if matches!(db_type, DatabaseType::Postgres) {
+                for column in table_info.columns.iter().filter(|c| c.is_auto_increment) {
+                    statements.push(format!(
+                        "SELECT setval(pg_get_serial_sequence({}, {}), \
+                         COALESCE(MAX({}), 0) + 1, false) FROM {}",
+                        db_type.quote_literal(&target),
+                        db_type.quote_literal(&column.name),
+                        db_type.quote_identifier(&column.name),
+                        target
+                    ));
+                }
+            }
+        }
+
+        let mut tx = self.pool.begin().await?;
+        for sql in &statements {
+            sqlx::query(sql).execute(&mut *tx).await?;
+        }
+        tx.commit().await
+    }
+
+    pub async fn drop_column(&self, request: &DropColumnRequest) -> Result<(), sqlx::Error> {
+        let db_type = &self.config.database_type;
+        let sql = format!(
+            "ALTER TABLE {} DROP COLUMN {}",
+            db_type.quote_qualified(&request.table),
+            db_type.quote_identifier(&request.column)
+        );
+        match sqlx::query(&sql).execute(self.pool).await {
+            // Older SQLite builds lack DROP COLUMN, and none can drop a key
+            // or indexed column.
+            Err(e) if matches!(db_type, DatabaseType::Sqlite) && sqlite_alter_unsupported(&e) => {
+                self.rebuild_sqlite_table(&request.table, &request.column, &ColumnChange::Drop)
+                    .await
+            }
+            result => result.map(|_| ()),
+        }
+    }
+
+    pub async fn rename_column(&self, request: &RenameColumnRequest) -> Result<(), sqlx::Error> {
+        let db_type = &self.config.database_type;
+        let sql = format!(
+            "ALTER TABLE {} RENAME COLUMN {} TO {}",
+            db_type.quote_qualified(&request.table),
+            db_type.quote_identifier(&request.old_name),
+            db_type.quote_identifier(&request.new_name)
+        );
+        match sqlx::query(&sql).execute(self.pool).await {
+            // SQLite before 3.25 has no RENAME COLUMN.
+            Err(e) if matches!(db_type, DatabaseType::Sqlite) && sqlite_alter_unsupported(&e) => {
+                let change = ColumnChange::Rename(request.new_name.clone());
+                self.rebuild_sqlite_table(&request.table, &request.old_name, &change)
+                    .await
+            }
+            result => result.map(|_| ()),
+        }
+    }
+
+    /// Change a column of a SQLite table in a way ALTER TABLE can't, by
+    /// rebuilding the table.
+    pub async fn rebuild_sqlite_table(
+        &self,
+        table: &QualifiedName,
+        column: &str,
+        change: &ColumnChange,
+    ) -> Result<(), sqlx::Error> {
+        let statements = self
+            .sqlite_rebuild_statements(table, column, change)
+            .await?;
+        self.run_sqlite_rebuild(&statements).await
+    }
+
+    /// Statements rebuilding a SQLite table with one column changed: create
+    /// the new definition under a temporary name, copy the rows across, drop
+    /// the original, rename the copy and recreate the indexes and triggers.
+    pub async fn sqlite_rebuild_statements(
+        &self,
+        table: &QualifiedName,
+        column: &str,
+        change: &ColumnChange,
+    ) -> Result<Vec<String>, sqlx::Error> {
+        let db_type = &self.config.database_type;
+        let create_sql = self.get_create_table_sql(table).await?;
+        let body = statement::create_table_body(&create_sql)
+            .ok_or_else(|| sqlx::Error::Protocol("unreadable table definition".into()))?;
+        let (definitions, options) = statement::table_definitions(body);
+
+        // Generated columns are computed, not copied.
+        let generated: Vec<String> =
+            sqlx::query_scalar("SELECT name FROM pragma_table_xinfo(?) WHERE hidden IN (2, 3)")
+                .bind(&table.name)
+                .fetch_all(self.pool)
+                .await?;
+        let is_generated = |name: &str| generated.iter().any(|g| g.eq_ignore_ascii_case(name));
+
+        let dropped = matches!(change, ColumnChange::Drop);
+        let new_name = match change {
+            ColumnChange::Rename(name) => Some(db_type.quote_identifier(name)),
+            ColumnChange::Drop | ColumnChange::Modify(_) => None,
+        };
+
+        let mut found = false;
+        let mut new_definitions = Vec::new();
+        let mut targets = Vec::new();
+        let mut sources = Vec::new();
+        for definition in &definitions {
+            match statement::column_name(definition) {
+                Some(name) if name.eq_ignore_ascii_case(column) => {
+                    found = true;
+                    let target = match change {
+                        ColumnChange::Drop => continue,
+                        ColumnChange::Rename(new) => {
+                            let quoted = db_type.quote_identifier(new);
+                            new_definitions.push(statement::rename_in_definition(
+                                definition,
+                                &table.name,
+                                column,
+                                &quoted,
+                            ));
+                            quoted
+                        }
+                        ColumnChange::Modify(new_definition) => {
+                            new_definitions.push(new_definition.clone());
+                            db_type.quote_identifier(&name)
+                        }
+                    };
+                    if !is_generated(&name) {
+                        targets.push(target);
+                        sources.push(db_type.quote_identifier(&name));
+                    }
+                }
+                Some(name) => {
+                    // Other columns' checks and generated values may use it.
+                    new_definitions.push(match &new_name {
+                        Some(new) => {
+                            statement::rename_in_definition(definition, &table.name, column, new)
+                        }
+                        None if dropped
+                            && statement::definition_uses_column(
+                                definition,
+                                &table.name,
+                                column,
+                            ) =>
+                        {
+                            return Err(sqlx::Error::Protocol(format!(
+                                "column {} is used by column {}",
+                                column, name
+                            )));
+                        }
+                        None => definition.clone(),
+                    });
+                    if !is_generated(&name) {
+                        targets.push(db_type.quote_identifier(&name));
+                        sources.push(db_type.quote_identifier(&name));
+                    }
+                }
+                // Like SQLite's own DROP COLUMN, refuse to drop a column a
+                // table constraint still needs.
+                None => match &new_name {
+                    Some(new) => new_definitions.push(statement::rename_in_definition(
+                        definition,
+                        &table.name,
+                        column,
+                        new,
+                    )),
+                    None if dropped
+                        && statement::definition_uses_column(definition, &table.name, column) =>
+                    {
+                        return Err(sqlx::Error::Protocol(format!(
+                            "column {} is used by constraint {}",
+                            column,
+                            statement::constraint_name(definition)
+                                .unwrap_or_else(|| definition.clone())
+                        )));
+                    }
+                    None => new_definitions.push(definition.clone()),
+                },
+            }
+        }
+        if !found {
+            return Err(sqlx::Error::ColumnNotFound(column.to_string()));
+        }
+        if targets.is_empty() && dropped {
+            return Err(sqlx::Error::Protocol(
+                "cannot drop the only column of a table".into(),
+            ));
+        }
+
+        // Views are left as they are, so they mustn't lose the column.
+        if dropped || new_name.is_some() {
+            let views: Vec<(String, String)> =
+                sqlx::query_as("SELECT name, sql FROM sqlite_master WHERE type = 'view'")
+                    .fetch_all(self.pool)
+                    .await?;
+            if let Some((view, _)) = views.iter().find(|(_, sql)| {
+                statement::mentions_identifier(sql, &table.name)
+                    && !statement::column_references(sql, column).is_empty()
+            }) {
+                return Err(sqlx::Error::Protocol(format!(
+                    "column {} is used by view {}",
+                    column, view
+                )));
+            }
+        }
+
+        let dependents: Vec<(String, String, String)> = sqlx::query_as(
+            "SELECT type, name, sql FROM sqlite_master \
+             WHERE tbl_name = ? AND type IN ('index', 'trigger') AND sql IS NOT NULL \
+             ORDER BY type",
+        )
+        .bind(&table.name)
+        .fetch_all(self.pool)
+        .await?;
+
+        let source = db_type.quote_qualified(table);
+        let temporary = db_type.quote_identifier(&format!("_rebuild_{}", table.name));
+        let mut statements = vec![
+            format!(
+                "CREATE TABLE {} (\n    {}\n) {}",
+                temporary,
+                new_definitions.join(",\n    "),
+                options
+            )
+            .trim_end()
+            .to_string(),
+            format!(
+                "INSERT INTO {} ({}) SELECT {} FROM {}",
+                temporary,
+                targets.join(", "),
+                sources.join(", "),
+                source
+            ),
+            format!("DROP TABLE {}", source),
+            format!(
+                "ALTER TABLE {} RENAME TO {}",
+                temporary,
+                db_type.quote_identifier(&table.name)
+            ),
+        ];
+        for (kind, name, sql) in dependents {
+            let is_index = kind == "index";
+            match &new_name {
+                Some(new) if is_index => {
+                    statements.push(statement::rename_in_index(&sql, column, new))
+                }
+                Some(new) => match statement::rename_in_trigger(&sql, &table.name, column, new) {
+                    Some(renamed) => statements.push(renamed),
+                    None => {
+                        return Err(sqlx::Error::Protocol(format!(
+                            "column {} is used by a trigger without NEW, OLD or the table name",
+                            column
+                        )));
+                    }
+                },
+                // An index on the dropped column alone goes with it; one
+                // that also needs other columns or a predicate is refused.
+                None if dropped && is_index => {
+                    let (terms, predicate) = statement::index_definition(&sql);
+                    let uses_column = terms
+                        .iter()
+                        .chain(predicate.iter())
+                        .any(|term| !statement::column_references(term, column).is_empty());
+                    if !uses_column {
+                        statements.push(sql);
+                    } else if terms.len() > 1 || predicate.is_some() {
+                        return Err(sqlx::Error::Protocol(format!(
+                            "column {} is used by index {}",
+                            column, name
+                        )));
+                    }
+                }
+                None if dropped && statement::trigger_uses_column(&sql, &table.name, column) => {
+                    return Err(sqlx::Error::Protocol(format!(
+                        "column {} is used by a trigger",
+                        column
+                    )));
+                }
+                None => statements.push(sql),
+            }
+        }
+        Ok(statements)
+    }
+
+    /// Run the statements of a SQLite rebuild in one transaction, with
+    /// foreign key enforcement suspended and the keys checked before commit.
+    async fn run_sqlite_rebuild(&self, statements: &[String]) -> Result<(), sqlx::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let foreign_keys: i64 = sqlx::query_scalar("PRAGMA foreign_keys")
+            .fetch_one(&mut *conn)
+            .await?;
+        // foreign_keys can't change inside a transaction. Legacy renaming
+        // keeps SQLite from checking views that point at the dropped table.
+        let result = async {
+            sqlx::query("PRAGMA foreign_keys = OFF")
+                .execute(&mut *conn)
+                .await?;
+            sqlx::query("PRAGMA legacy_alter_table = ON")
+                .execute(&mut *conn)
+                .await?;
+
+            let mut tx = conn.begin().await?;
+            // Only violations the rebuild introduces count.
+            let check = "PRAGMA foreign_key_check";
+            let before = sqlx::query(check).fetch_all(&mut *tx).await?.len();
+            for sql in statements {
+                sqlx::query(sql).execute(&mut *tx).await?;
+            }
+            if foreign_keys != 0 && sqlx::query(check).fetch_all(&mut *tx).await?.len() > before {
+                return Err(sqlx::Error::Protocol(
+                    "the rebuilt table violates a foreign key constraint".into(),
+                ));
+            }
+            tx.commit().await
+        }
+        .await;
+
+        // Both pragmas are put back whatever happened above.
+        let legacy_restored = sqlx::query("PRAGMA legacy_alter_table = OFF")
+            .execute(&mut *conn)
+            .await
+            .map(|_| ());
+        let foreign_keys_restored = match foreign_keys {
+            0 => Ok(()),
+            _ => sqlx::query("PRAGMA foreign_keys = ON")
+                .execute(&mut *conn)
+                .await
+                .map(|_| ()),
+        };
+        if legacy_restored.is_err() || foreign_keys_restored.is_err() {
+            // Never hand a connection with the pragmas still changed back to
+            // the pool; it is closed when dropped.
+            drop(conn.detach());
+        }
+        result.and(legacy_restored).and(foreign_keys_restored)
+    }
+
+    /// Statements that give a column the type, nullability and default of
+    /// `request`, altering only what changed. Postgres alters the column in
+    /// place; MySQL restates its definition and SQLite rebuilds the table,
+    /// both keeping the clauses the form doesn't cover.
+    pub async fn modify_column_statements(
+        &self,
+        request: &ModifyColumnRequest,
+    ) -> Result<Vec<String>, sqlx::Error> {
+        let db_type = &self.config.database_type;
+        let table_info = self.get_table_info(&request.table).await?;
+        let current = table_info
+            .columns
+            .iter()
+            .find(|c| c.name == request.column)
+            .ok_or_else(|| sqlx::Error::ColumnNotFound(request.column.clone()))?;
+
+        let type_changed = !request.data_type.eq_ignore_ascii_case(&current.data_type);
+        let nullable_changed = request.nullable != current.nullable;
+        let default_changed = match (&request.default_value, &current.default_value) {
+            (Some(entered), Some(current)) => {
+                entered != current && !db_type.same_default(entered, current)
+            }
+            (entered, current) => entered.is_some() != current.is_some(),
+        };
+        if !type_changed && !nullable_changed && !default_changed {
+            return Ok(vec![]);
+        }
+
+        let table = db_type.quote_qualified(&request.table);
+        let column = db_type.quote_identifier(&request.column);
+        if let DatabaseType::Postgres = db_type {
+            let mut actions = Vec::new();
+            if type_changed {
+                // The old default is dropped first, as it may not convert to
+                // the new type.
+                if current.default_value.is_some() {
+                    actions.push(format!("ALTER COLUMN {} DROP DEFAULT", column));
+                }
+                actions.push(format!(
+                    "ALTER COLUMN {} TYPE {} USING {}::{}",
+                    column, request.data_type, column, request.data_type
+                ));
+            }
+            if nullable_changed {
+                actions.push(format!(
+                    "ALTER COLUMN {} {} NOT NULL",
+                    column,
+                    if request.nullable { "DROP" } else { "SET" }
+                ));
+            }
+            if default_changed {
+                actions.push(match &request.default_value {
+                    Some(value) => format!(
+                        "ALTER COLUMN {} SET DEFAULT {}",
+                        column,
+                        db_type.default_expression(value)
+                    ),
+                    None => format!("ALTER COLUMN {} DROP DEFAULT", column),
+                });
+            } else if type_changed && let Some(default) = &current.default_value {
+                // An unchanged default is restored as it was written.
+                actions.push(format!("ALTER COLUMN {} SET DEFAULT {}", column, default));
+            }
+            return Ok(vec![format!(
+                "ALTER TABLE {} {}",
+                table,
+                actions.join(", ")
+            )]);
+        }
+
+        // Rewrite the column's definition as the table was created.
+        let create_sql = self.get_create_table_sql(&request.table).await?;
+        let definition = statement::create_table_body(&create_sql)
+            .map(|body| statement::table_definitions(body).0)
+            .unwrap_or_default()
+            .into_iter()
+            .find(|d| {
+                statement::column_name(d)
+                    .is_some_and(|name| name.eq_ignore_ascii_case(&request.column))
+            })
+            .ok_or_else(|| sqlx::Error::ColumnNotFound(request.column.clone()))?;
+
+        let (head, clauses) = statement::column_clauses(&definition);
+        let mut parts = vec![if type_changed {
+            format!("{} {}", column, request.data_type)
+        } else {
+            head
+        }];
+        for clause in clauses {
+            let upper = clause.to_ascii_uppercase();
+            let replaced = (nullable_changed && (upper == "NULL" || upper.starts_with("NOT NULL")))
+                || (default_changed && upper.starts_with("DEFAULT"));
+            if !replaced {
+                parts.push(clause);
+            }
+        }
+        if nullable_changed && !request.nullable {
+            parts.push("NOT NULL".to_string());
+        }
+        if default_changed && let Some(value) = &request.default_value {
+            parts.push(format!("DEFAULT {}", db_type.default_expression(value)));
+        }
+        let new_definition = parts.join(" ");
+
+        match db_type {
+            DatabaseType::Mysql => Ok(vec![format!(
+                "ALTER TABLE {} MODIFY COLUMN {}",
+                table, new_definition
+            )]),
+            _ => {
+                self.sqlite_rebuild_statements(
+                    &request.table,
+                    &request.column,
+                    &ColumnChange::Modify(new_definition),
+                )
+                .await
+            }
+        }
+    }
+
+    pub async fn modify_column(&self, request: &ModifyColumnRequest) -> Result<(), sqlx::Error> {
+        let statements = self.modify_column_statements(request).await?;
+        match self.config.database_type {
+            DatabaseType::Sqlite => self.run_sqlite_rebuild(&statements).await,
+            DatabaseType::Mysql | DatabaseType::Postgres => {
+                for sql in &statements {
+                    sqlx::query(sql).execute(self.pool).await?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn create_index(&self, request: &CreateIndexRequest) -> Result<(), sqlx::Error> {
+        let db_type = &self.config.database_type;
+        let columns: Vec<String> = request
+            .columns
+            .iter()
+            .map(|c| db_type.quote_identifier(c))
+            .collect();
+        let sql = format!(
+            "CREATE {}INDEX {} ON {} ({})",
+            if request.unique { "UNIQUE " } else { "" },
+            db_type.quote_identifier(&request.name),
+            db_type.quote_qualified(&request.table),
+            columns.join(", ")
+        );
+        sqlx::query(&sql).execute(self.pool).await?;
+        Ok(())
+    }
+
+    pub async fn drop_index(&self, request: &DropIndexRequest) -> Result<(), sqlx::Error> {
+        let db_type = &self.config.database_type;
+        let sql = match db_type {
+            // MySQL indexes are scoped to their table.
+            DatabaseType::Mysql => format!(
+                "DROP INDEX {} ON {}",
+                db_type.quote_identifier(&request.name),
+                db_type.quote_qualified(&request.table)
+            ),
+            DatabaseType::Sqlite => {
+                format!("DROP INDEX {}", db_type.quote_identifier(&request.name))
+            }
+            // Postgres indexes live in their table's schema.
+            DatabaseType::Postgres => format!(
+                "DROP INDEX {}",
+                db_type.quote_qualified(&QualifiedName::new(
+                    request.table.schema.clone(),
+                    &request.name
+                ))
+            ),
+        };
+        sqlx::query(&sql).execute(self.pool).await?;
+        Ok(())
+    }
+
+    pub async fn get_indexes(&self, table: &QualifiedName) -> Result<Vec<IndexInfo>, sqlx::Error> {
+        match self.config.database_type {
+            DatabaseType::Sqlite => self.get_sqlite_indexes(&table.name).await,
+            DatabaseType::Mysql => self.get_mysql_indexes(&table.name).await,
+            DatabaseType::Postgres => self.get_postgres_indexes(table).await,
+        }
+    }
+
+    /// Foreign keys declared on `table`.
+    pub async fn get_foreign_keys(
+        &self,
+        table: &QualifiedName,
+    ) -> Result<Vec<ForeignKeyInfo>, sqlx::Error> {
+        self.find_foreign_keys(table, false).await
+    }
+
+    /// Foreign keys of other tables (or self-references) pointing at `table`.
+    pub async fn get_referencing_foreign_keys(
+        &self,
+        table: &QualifiedName,
+    ) -> Result<Vec<ForeignKeyInfo>, sqlx::Error> {
+        self.find_foreign_keys(table, true).await
+    }
+
+    /// Foreign keys declared on `table`, or referencing it when `incoming` is
+    /// set. Rows come one per key column, ordered so the columns of each key
+    /// are adjacent and in key order.
+    async fn find_foreign_keys(
+        &self,
+        table: &QualifiedName,
+        incoming: bool,
+    ) -> Result<Vec<ForeignKeyInfo>, sqlx::Error> {
+        let sql = match self.config.database_type {
+            // SQLite keys are unnamed, so they are grouped by their id.
+            DatabaseType::Sqlite => format!(
+                r#"
+                SELECT CAST(f.id AS TEXT) AS name, m.name AS table_name,
+                    f."from" AS column_name, f."table" AS referenced_table,
+                    f."to" AS referenced_column, f.on_update, f.on_delete
+                FROM sqlite_master m
+                JOIN pragma_foreign_key_list(m.name) f
+                WHERE m.type = 'table' AND {} = ? COLLATE NOCASE
+                ORDER BY m.name, f.id, f.seq
+                "#,
+                if incoming { r#"f."table""# } else { "m.name" }
+            ),
+            DatabaseType::Mysql => format!(
+                r#"
+                SELECT
+                    CAST(k.CONSTRAINT_NAME AS CHAR) AS name,
+                    CAST(k.TABLE_NAME AS CHAR) AS table_name,
+                    CAST(k.COLUMN_NAME AS CHAR) AS column_name,
+                    CAST(k.REFERENCED_TABLE_NAME AS CHAR) AS referenced_table,
+                    CAST(k.REFERENCED_COLUMN_NAME AS CHAR) AS referenced_column,
+                    CAST(r.UPDATE_RULE AS CHAR) AS on_update,
+                    CAST(r.DELETE_RULE AS CHAR) AS on_delete
+                FROM information_schema.key_column_usage k
+                JOIN information_schema.referential_constraints r
+                    ON r.CONSTRAINT_SCHEMA = k.CONSTRAINT_SCHEMA
+                    AND r.CONSTRAINT_NAME = k.CONSTRAINT_NAME
+                    AND r.TABLE_NAME = k.TABLE_NAME
+                WHERE k.TABLE_SCHEMA = DATABASE()
+                    AND k.REFERENCED_TABLE_NAME IS NOT NULL
+                    AND k.{} = ?
+                ORDER BY k.TABLE_NAME, k.CONSTRAINT_NAME, k.ORDINAL_POSITION
+                "#,
+                if incoming {
+                    "REFERENCED_TABLE_NAME"
+                } else {
+                    "TABLE_NAME"
+                }
+            ),
+            // conkey and confkey pair up the key columns in order.
+            DatabaseType::Postgres => format!(
+                r#"
+                SELECT
+                    c.conname::text AS name,
+                    t.relname::text AS table_name,
+                    a.attname::text AS column_name,
+                    r.relname::text AS referenced_table,
+                    CASE WHEN r.relnamespace <> t.relnamespace
+                        THEN rn.nspname::text END AS referenced_schema,
+                    ra.attname::text AS referenced_column,
+                    CASE c.confupdtype WHEN 'r' THEN 'RESTRICT' WHEN 'c' THEN 'CASCADE'
+                        WHEN 'n' THEN 'SET NULL' WHEN 'd' THEN 'SET DEFAULT'
+                        ELSE 'NO ACTION' END AS on_update,
+                    CASE c.confdeltype WHEN 'r' THEN 'RESTRICT' WHEN 'c' THEN 'CASCADE'
+                        WHEN 'n' THEN 'SET NULL' WHEN 'd' THEN 'SET DEFAULT'
+                        ELSE 'NO ACTION' END AS on_delete
+                FROM pg_constraint c
+                JOIN pg_class t ON t.oid = c.conrelid
+                JOIN pg_class r ON r.oid = c.confrelid
+                JOIN pg_namespace rn ON rn.oid = r.relnamespace
+                CROSS JOIN LATERAL unnest(c.conkey, c.confkey) WITH ORDINALITY AS k(attnum, ref_attnum, n)
+                JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = k.attnum
+                JOIN pg_attribute ra ON ra.attrelid = c.confrelid AND ra.attnum = k.ref_attnum
+                WHERE c.contype = 'f' AND {}.relname = $1 AND {}
+                ORDER BY t.relname, c.conname, k.n
+                "#,
+                if incoming { "r" } else { "t" },
+                self.pg_namespace_condition(table, if incoming { "r" } else { "t" })
+            ),
+        };
+
+        let rows = sqlx::query(&sql)
+            .bind(&table.name)
+            .fetch_all(self.pool)
+            .await?;
+
+        let mut foreign_keys: Vec<ForeignKeyInfo> = Vec::new();
+        for row in rows {
+            let name: String = row.try_get("name")?;
+            let table: String = row.try_get("table_name")?;
+            let column: String = row.try_get("column_name")?;
+            // SQLite leaves out the referenced column when it is the primary key.
+            let referenced_column: Option<String> = row.try_get("referenced_column").ok().flatten();
+
+            match foreign_keys.last_mut() {
+                Some(fk) if fk.name == name && fk.table == table => {
+                    fk.columns.push(column);
+                    fk.referenced_columns.extend(referenced_column);
+                }
+                _ => foreign_keys.push(ForeignKeyInfo {
+                    name,
+                    table,
+                    columns: vec![column],
+                    referenced_table: row.try_get("referenced_table")?,
+                    referenced_schema: row.try_get("referenced_schema").ok().flatten(),
+                    referenced_columns: referenced_column.into_iter().collect(),
+                    on_update: row.try_get("on_update")?,
+                    on_delete: row.try_get("on_delete")?,
+                }),
+            }
+        }
+
+        if matches!(self.config.database_type, DatabaseType::Sqlite) {
+            for fk in &mut foreign_keys {
+                if fk.referenced_columns.is_empty() {
+                    fk.referenced_columns = sqlx::query_scalar(
+                        "SELECT name FROM pragma_table_info(?) WHERE pk > 0 ORDER BY pk",
+                    )
+                    .bind(&fk.referenced_table)
+                    .fetch_all(self.pool)
+                    .await?;
+                }
+                // The id only groups columns; SQLite doesn't keep constraint names.
+                fk.name.clear();
+            }
+        }
+
+        Ok(foreign_keys)
+    }
+
+    /// The CREATE TABLE statement of `table`. Postgres keeps no DDL, so there
+    /// it is rebuilt from the catalog.
+    pub async fn get_create_table_sql(&self, table: &QualifiedName) -> Result<String, sqlx::Error> {
+        let db_type = &self.config.database_type;
+        match db_type {
+            DatabaseType::Sqlite => {
+                sqlx::query_scalar(
+                    "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = ?",
+                )
+                .bind(&table.name)
+                .fetch_one(self.pool)
+                .await
+            }
+            DatabaseType::Mysql => {
+                let sql = format!(
+                    "SHOW CREATE TABLE {}",
+                    db_type.quote_identifier(&table.name)
+                );
+                let row = sqlx::query(&sql).fetch_one(self.pool).await?;
+                // Returned as binary under some connection character sets.
+                row.try_get::<String, _>(1).or_else(|_| {
+                    row.try_get::<Vec<u8>, _>(1)
+                        .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+                })
+            }
+            DatabaseType::Postgres => self.get_postgres_create_table_sql(table).await,
+        }
+    }
+
+    async fn get_postgres_create_table_sql(
+        &self,
+        table: &QualifiedName,
+    ) -> Result<String, sqlx::Error> {
+        let db_type = &self.config.database_type;
+        let relation = format!(
+            "(SELECT c.oid FROM pg_class c WHERE c.relname = $1 AND {})",
+            self.pg_namespace_condition(table, "c")
+        );
+
+        let column_rows = sqlx::query(&format!(
+            r#"
+            SELECT
+                a.attname::text AS name,
+                format_type(a.atttypid, a.atttypmod) AS data_type,
+                CASE WHEN a.attnotnull THEN 1 ELSE 0 END AS not_null,
+                pg_get_expr(d.adbin, d.adrelid) AS default_value,
+                a.attidentity::text AS identity,
+                a.attgenerated::text AS generated,
+                CASE WHEN a.attcollation <> t.typcollation
+                    THEN quote_ident(co.collname::text) END AS collation
+            FROM pg_attribute a
+            JOIN pg_type t ON t.oid = a.atttypid
+            LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+            LEFT JOIN pg_collation co ON co.oid = a.attcollation
+            WHERE a.attrelid = {} AND a.attnum > 0 AND NOT a.attisdropped
+            ORDER BY a.attnum
+            "#,
+            relation
+        ))
+        .bind(&table.name)
+        .fetch_all(self.pool)
+        .await?;
+
+        let mut definitions = Vec::new();
+        for row in column_rows {
+            let name: String = row.try_get("name")?;
+            let data_type: String = row.try_get("data_type")?;
+            let not_null: i32 = row.try_get("not_null")?;
+            let default_value: Option<String> = row.try_get("default_value").ok().flatten();
+            let identity: String = row.try_get("identity")?;
+            let generated: String = row.try_get("generated")?;
+            let collation: Option<String> = row.try_get("collation").ok().flatten();
+
+            let mut definition = format!("{} {}", db_type.quote_identifier(&name), data_type);
+            if let Some(collation) = collation {
+                definition.push_str(&format!(" COLLATE {}", collation));
+            }
+            match (identity.as_str(), generated.as_str(), default_value) {
+                ("a", _, _) => definition.push_str(" GENERATED ALWAYS AS IDENTITY"),
+                ("d", _, _) => definition.push_str(" GENERATED BY DEFAULT AS IDENTITY"),
+                (_, "s", Some(expression)) => {
+                    definition.push_str(&format!(" GENERATED ALWAYS AS ({}) STORED", expression))
+                }
+                (_, _, Some(default_value)) => {
+                    definition.push_str(&format!(" DEFAULT {}", default_value))
+                }
+                _ => {}
+            }
+            if not_null != 0 {
+                definition.push_str(" NOT NULL");
+            }
+            definitions.push(definition);
+        }
+
+        // NOT NULL constraints (contype 'n' on Postgres 18) are already on
+        // the columns.
+        let constraint_rows = sqlx::query(&format!(
+            r#"
+            SELECT conname::text AS name, pg_get_constraintdef(oid) AS definition
+            FROM pg_constraint
+            WHERE conrelid = {} AND contype <> 'n'
+            ORDER BY CASE contype WHEN 'p' THEN 0 WHEN 'u' THEN 1 WHEN 'c' THEN 2
+                WHEN 'f' THEN 3 ELSE 4 END, conname
+            "#,
+            relation
+        ))
+        .bind(&table.name)
+        .fetch_all(self.pool)
+        .await?;
+
+        for row in constraint_rows {
+            let name: String = row.try_get("name")?;
+            let definition: String = row.try_get("definition")?;
+            definitions.push(format!(
+                "CONSTRAINT {} {}",
+                db_type.quote_identifier(&name),
+                definition
+            ));
+        }
+
+        let mut sql = format!(
+            "CREATE TABLE {} (\n    {}\n);",
+            db_type.quote_qualified(table),
+            definitions.join(",\n    ")
+        );
+
+        // Indexes backing a constraint come with the constraint.
+        let indexes: Vec<String> = sqlx::query_scalar(&format!(
+            r#"
+            SELECT pg_get_indexdef(i.indexrelid)
+            FROM pg_index i
+            WHERE i.indrelid = {}
+                AND NOT EXISTS (
+                    SELECT 1 FROM pg_constraint c
+                    WHERE c.conrelid = i.indrelid AND c.conindid = i.indexrelid
+                )
+            ORDER BY i.indexrelid
+            "#,
+            relation
+        ))
+        .bind(&table.name)
+        .fetch_all(self.pool)
+        .await?;
+        for index in indexes {
+            sql.push_str(&format!("\n{};", index));
+        }
+
+        Ok(sql)
+    }
+
+    /// Triggers on `table`, or on every table when `None`.
+    pub async fn get_triggers(
+        &self,
+        table: Option<&QualifiedName>,
+    ) -> Result<Vec<TriggerInfo>, sqlx::Error> {
+        let sql = match self.config.database_type {
+            DatabaseType::Sqlite => format!(
+                r#"
+                SELECT name, tbl_name AS table_name, sql AS definition
+                FROM sqlite_master
+                WHERE type = 'trigger'{}
+                ORDER BY tbl_name, name
+                "#,
+                if table.is_some() {
+                    " AND tbl_name = ?"
+                } else {
+                    ""
+                }
+            ),
+            DatabaseType::Mysql => format!(
+                r#"
+                SELECT
+                    CAST(TRIGGER_NAME AS CHAR) AS name,
+                    CAST(EVENT_OBJECT_TABLE AS CHAR) AS table_name,
+                    CAST(ACTION_TIMING AS CHAR) AS timing,
+                    CAST(EVENT_MANIPULATION AS CHAR) AS event,
+                    CAST(ACTION_STATEMENT AS CHAR) AS definition
+                FROM information_schema.triggers
+                WHERE TRIGGER_SCHEMA = DATABASE(){}
+                ORDER BY EVENT_OBJECT_TABLE, TRIGGER_NAME
+                "#,
+                if table.is_some() {
+                    " AND EVENT_OBJECT_TABLE = ?"
+                } else {
+                    ""
+                }
+            ),
+            // tgtype is a bitmask: 2 BEFORE, 64 INSTEAD OF, then 4 INSERT,
+            // 8 DELETE, 16 UPDATE and 32 TRUNCATE.
+            DatabaseType::Postgres => format!(
+                r#"
+                SELECT
+                    t.tgname::text AS name,
+                    c.relname::text AS table_name,
+                    CASE WHEN t.tgtype & 2 <> 0 THEN 'BEFORE'
+                        WHEN t.tgtype & 64 <> 0 THEN 'INSTEAD OF'
+                        ELSE 'AFTER' END AS timing,
+                    concat_ws(' OR ',
+                        CASE WHEN t.tgtype & 4 <> 0 THEN 'INSERT' END,
+                        CASE WHEN t.tgtype & 8 <> 0 THEN 'DELETE' END,
+                        CASE WHEN t.tgtype & 16 <> 0 THEN 'UPDATE' END,
+                        CASE WHEN t.tgtype & 32 <> 0 THEN 'TRUNCATE' END) AS event,
+                    pg_get_triggerdef(t.oid) AS definition
+                FROM pg_trigger t
+                JOIN pg_class c ON c.oid = t.tgrelid
+                JOIN pg_namespace n ON n.oid = c.relnamespace
+                WHERE NOT t.tgisinternal AND {}
+                ORDER BY c.relname, t.tgname
+                "#,
+                match table {
+                    Some(table) => format!(
+                        "c.relname = $1 AND {}",
+                        self.pg_namespace_condition(table, "c")
+                    ),
+                    None => self.pg_schema_condition("n"),
+                }
+            ),
+        };
+
+        let mut query = sqlx::query(&sql);
+        if let Some(table) = table {
+            query = query.bind(&table.name);
+        }
+        let rows = query.fetch_all(self.pool).await?;
+
+        let db_type = &self.config.database_type;
+        let mut triggers = Vec::new();
+        for row in rows {
+            let name: String = row.try_get("name")?;
+            let table: String = row.try_get("table_name")?;
+            let definition: String = row.try_get("definition")?;
+            let trigger = match db_type {
+                // SQLite only keeps the statement text.
+                DatabaseType::Sqlite => {
+                    let (timing, event) = statement::trigger_timing_event(&definition);
+                    TriggerInfo {
+                        name,
+                        table,
+                        timing,
+                        event,
+                        definition,
+                    }
+                }
+                // information_schema only has the body; rebuild the statement.
+                DatabaseType::Mysql => {
+                    let timing: String = row.try_get("timing")?;
+                    let event: String = row.try_get("event")?;
+                    let definition = format!(
+                        "CREATE TRIGGER {} {} {} ON {} FOR EACH ROW {}",
+                        db_type.quote_identifier(&name),
+                        timing,
+                        event,
+                        db_type.quote_identifier(&table),
+                        definition
+                    );
+                    TriggerInfo {
+                        name,
+                        table,
+                        timing,
+                        event,
+                        definition,
+                    }
+                }
+                DatabaseType::Postgres => TriggerInfo {
+                    name,
+                    table,
+                    timing: row.try_get("timing")?,
+                    event: row.try_get("event")?,
+                    definition,
+                },
+            };
+            triggers.push(trigger);
+        }
+        Ok(triggers)
+    }
+
+    /// Run a script creating a trigger, together with any function it needs,
+    /// in one transaction.
+    pub async fn create_trigger(&self, statements: &[String]) -> Result<(), sqlx::Error> {
+        let results = self.run_script(statements, true).await?;
+        match results.into_iter().find_map(|r| r.result.err()) {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    pub async fn drop_trigger(&self, table: &QualifiedName, name: &str) -> Result<(), sqlx::Error> {
+        let db_type = &self.config.database_type;
+        let sql = match db_type {
+            // Postgres triggers are scoped to their table.
+            DatabaseType::Postgres => format!(
+                "DROP TRIGGER {} ON {}",
+                db_type.quote_identifier(name),
+                db_type.quote_qualified(table)
+            ),
+            DatabaseType::Sqlite | DatabaseType::Mysql => {
+                format!("DROP TRIGGER {}", db_type.quote_identifier(name))
+            }
+        };
+        sqlx::query(&sql).execute(self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_sqlite_indexes(&self, table_name: &str) -> Result<Vec<IndexInfo>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT l.name, l."unique", l.origin, m.sql
+            FROM pragma_index_list(?) l
+            LEFT JOIN sqlite_master m ON m.type = 'index' AND m.name = l.name
+            ORDER BY l.seq
+            "#,
+        )
+        .bind(table_name)
+        .fetch_all(self.pool)
+        .await?;
+
+        let mut indexes = Vec::new();
+        for row in rows {
+            let name: String = row.try_get("name")?;
+            let unique: i32 = row.try_get("unique")?;
+            let origin: String = row.try_get("origin")?;
+            // Indexes created for constraints have no SQL of their own.
+            let sql: Option<String> = row.try_get("sql").ok().flatten();
+            let (terms, predicate) = sql
+                .as_deref()
+                .map(statement::index_definition)
+                .unwrap_or_default();
+
+            // Auxiliary columns (key = 0) hold the rowid, not part of the key.
+            let column_rows = sqlx::query(
+                r#"SELECT seqno, cid, name, "desc" FROM pragma_index_xinfo(?) WHERE key = 1 ORDER BY seqno"#,
+            )
+            .bind(&name)
+            .fetch_all(self.pool)
+            .await?;
+
+            let mut columns = Vec::new();
+            for column_row in column_rows {
+                let seqno: i32 = column_row.try_get("seqno")?;
+                let cid: i32 = column_row.try_get("cid")?;
+                let descending: i32 = column_row.try_get("desc")?;
+                // cid -2 marks an expression, -1 the rowid.
+                let (column, expression) = match cid {
+                    -2 => (
+                        None,
+                        Some(
+                            terms
+                                .get(seqno as usize)
+                                .map(|term| strip_sort_order(term))
+                                .unwrap_or_else(|| "<expression>".to_string()),
+                        ),
+                    ),
+                    _ => (column_row.try_get("name").ok(), None),
+                };
+                columns.push(IndexColumn {
+                    name: column,
+                    expression,
+                    descending: descending != 0,
+                });
+            }
+
+            indexes.push(IndexInfo {
+                name,
+                unique: unique != 0,
+                primary: origin == "pk",
+                from_constraint: origin != "c",
+                method: "BTREE".to_string(),
+                columns,
+                predicate,
+            });
+        }
+        Ok(indexes)
+    }
+
+    async fn get_mysql_indexes(&self, table_name: &str) -> Result<Vec<IndexInfo>, sqlx::Error> {
+        // EXPRESSION only exists from MySQL 8.0.13; older servers and MariaDB
+        // have no functional indexes.
+        let query = |expression: &str| {
+            format!(
+                r#"
+                SELECT
+                    CAST(INDEX_NAME AS CHAR) AS index_name,
+                    CAST(NON_UNIQUE AS SIGNED) AS non_unique,
+                    CAST(INDEX_TYPE AS CHAR) AS index_type,
+                    CAST(COLUMN_NAME AS CHAR) AS column_name,
+                    CAST({} AS CHAR) AS expression,
+                    CAST(COLLATION AS CHAR) AS collation,
+                    CAST(SUB_PART AS SIGNED) AS sub_part
+                FROM information_schema.statistics
+                WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ?
+                ORDER BY INDEX_NAME = 'PRIMARY' DESC, INDEX_NAME, SEQ_IN_INDEX
+                "#,
+                expression
+            )
+        };
+        let with_expressions = query("EXPRESSION");
+        let rows = match sqlx::query(&with_expressions)
+            .bind(table_name)
+            .fetch_all(self.pool)
+            .await
+        {
+            Ok(rows) => rows,
+            Err(_) => {
+                let without_expressions = query("NULL");
+                sqlx::query(&without_expressions)
+                    .bind(table_name)
+                    .fetch_all(self.pool)
+                    .await?
+            }
+        };
+
+        let mut indexes: Vec<IndexInfo> = Vec::new();
+        for row in rows {
+            let name: String = row.try_get("index_name")?;
+            let non_unique: i64 = row.try_get("non_unique")?;
+            let method: String = row.try_get("index_type")?;
+            let column: Option<String> = row.try_get("column_name").ok().flatten();
+            let expression: Option<String> = row.try_get("expression").ok().flatten();
+            let collation: Option<String> = row.try_get("collation").ok().flatten();
+            let sub_part: Option<i64> = row.try_get("sub_part").ok().flatten();
+
+            // Prefix indexes only cover the first `sub_part` characters.
+            let column = match sub_part {
+                Some(length) => column.map(|c| format!("{}({})", c, length)),
+                None => column,
+            };
+            let index_column = IndexColumn {
+                name: column,
+                expression,
+                descending: collation.as_deref() == Some("D"),
+            };
+
+            match indexes.last_mut() {
+                Some(index) if index.name == name => index.columns.push(index_column),
+                _ => indexes.push(IndexInfo {
+                    primary: name == "PRIMARY",
+                    from_constraint: name == "PRIMARY",
+                    name,
+                    unique: non_unique == 0,
+                    method,
+                    columns: vec![index_column],
+                    predicate: None,
+                }),
+            }
+        }
+        Ok(indexes)
+    }
+
+    async fn get_postgres_indexes(
+        &self,
+        table: &QualifiedName,
+    ) -> Result<Vec<IndexInfo>, sqlx::Error> {
+        // One row per key column; indkey and indoption are 0-based vectors.
+        let sql = format!(
+            r#"
+            SELECT
+                i.relname::text AS index_name,
+                CASE WHEN ix.indisunique THEN 1 ELSE 0 END AS is_unique,
+                CASE WHEN ix.indisprimary THEN 1 ELSE 0 END AS is_primary,
+                CASE WHEN EXISTS (
+                    SELECT 1 FROM pg_constraint c WHERE c.conindid = ix.indexrelid
+                ) THEN 1 ELSE 0 END AS from_constraint,
+                am.amname::text AS method,
+                pg_get_expr(ix.indpred, ix.indrelid) AS predicate,
+                a.attname::text AS column_name,
+                pg_get_indexdef(ix.indexrelid, k.n, true) AS definition,
+                CASE WHEN ix.indoption[k.n - 1] & 1 = 1 THEN 1 ELSE 0 END AS is_descending
+            FROM pg_index ix
+            JOIN pg_class i ON i.oid = ix.indexrelid
+            JOIN pg_class t ON t.oid = ix.indrelid
+            JOIN pg_am am ON am.oid = i.relam
+            CROSS JOIN LATERAL generate_series(1, ix.indnkeyatts) AS k(n)
+            LEFT JOIN pg_attribute a
+                ON a.attrelid = t.oid AND a.attnum = ix.indkey[k.n - 1] AND ix.indkey[k.n - 1] <> 0
+            WHERE t.relname = $1 AND {}
+            ORDER BY ix.indisprimary DESC, i.relname, k.n
+            "#,
+            self.pg_namespace_condition(table, "t")
+        );
+        let rows = sqlx::query(&sql)
+            .bind(&table.name)
+            .fetch_all(self.pool)
+            .await?;
+
+        let mut indexes: Vec<IndexInfo> = Vec::new();
+        for row in rows {
+            let name: String = row.try_get("index_name")?;
+            let column: Option<String> = row.try_get("column_name").ok().flatten();
+            let definition: String = row.try_get("definition")?;
+            let descending: i32 = row.try_get("is_descending")?;
+
+            let index_column = IndexColumn {
+                expression: column.is_none().then_some(definition),
+                name: column,
+                descending: descending != 0,
+            };
+
+            match indexes.last_mut() {
+                Some(index) if index.name == name => index.columns.push(index_column),
+                _ => {
+                    let unique: i32 = row.try_get("is_unique")?;
+                    let primary: i32 = row.try_get("is_primary")?;
+                    let from_constraint: i32 = row.try_get("from_constraint")?;
+                    let method: String = row.try_get("method")?;
+                    indexes.push(IndexInfo {
+                        name,
+                        unique: unique != 0,
+                        primary: primary != 0,
+                        from_constraint: from_constraint != 0,
+                        method: method.to_uppercase(),
+                        columns: vec![index_column],
+                        predicate: row.try_get("predicate").ok().flatten(),
+                    });
+                }
+            }
+        }
+        Ok(indexes)
+    }
+}
+
+#[derive(Debug, Clone)]
+#[allow(unused)]
+pub struct TableInfo {
+    pub name: String,
+    pub schema: Option<String>,
+    pub columns: Vec<ColumnInfo>,
+    pub is_view: bool,
+}
+
+impl TableInfo {
+    pub fn qualified_name(&self) -> QualifiedName {
+        QualifiedName::new(self.schema.clone(), &self.name)
+    }
+}
+
+/// Length, precision and scale given with a declared type, as in
+/// `VARCHAR(255)` or `DECIMAL(10,2)`. SQLite keeps nothing else to go by.
+fn type_sizes(data_type: &str) -> (Option<i64>, Option<i64>, Option<i64>) {
+    let upper = data_type.to_ascii_uppercase();
+    let sizes: Vec<i64> = upper
+        .split_once('(')
+        .and_then(|(_, rest)| rest.split_once(')'))
+        .map(|(args, _)| {
+            args.split(',')
+                .filter_map(|a| a.trim().parse().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    let textual = ["CHAR", "CLOB", "TEXT"].iter().any(|t| upper.contains(t));
+    let numeric = ["DEC", "NUMERIC"].iter().any(|t| upper.contains(t));
+    match sizes.as_slice() {
+        [length, ..] if textual => (Some(*length), None, None),
+        [precision] if numeric => (None, Some(*precision), Some(0)),
+        [precision, scale, ..] if numeric => (None, Some(*precision), Some(*scale)),
+        _ => (None, None, None),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ColumnInfo {
+    pub name: String,
+    pub data_type: String,
+    pub nullable: bool,
+    pub default_value: Option<String>,
+    pub is_primary_key: bool,
+    /// Filled in by the database: AUTO_INCREMENT, an identity or serial
+    /// column, or SQLite's rowid alias.
+    pub is_auto_increment: bool,
+    pub max_length: Option<i64>,
+    pub numeric_precision: Option<i64>,
+    pub numeric_scale: Option<i64>,
+    /// Only set when it differs from the type's default.
+    pub collation: Option<String>,
+    /// Expression of a generated column.
+    pub generated: Option<String>,
+    pub comment: Option<String>,
+}
+
+impl ColumnInfo {
+    /// A nullable column with nothing known beyond its name and type.
+    pub fn new(name: &str, data_type: &str) -> Self {
+        ColumnInfo {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            nullable: true,
+            default_value: None,
+            is_primary_key: false,
+            is_auto_increment: false,
+            max_length: None,
+            numeric_precision: None,
+            numeric_scale: None,
+            collation: None,
+            generated: None,
+            comment: None,
+        }
+    }
+
+    /// Form widget suited to the column's type: `integer`, `number`,
+    /// `boolean`, `date`, `time`, `datetime`, `json`, `blob`, `textarea` or
+    /// `text`. Types carrying a time zone stay plain text.
+    pub fn input_kind(&self) -> &'static str {
+        let data_type = self.data_type.to_lowercase();
+        if data_type.starts_with("bool") || data_type == "tinyint(1)" {
+            return "boolean";
+        }
+        let with_time_zone = data_type.contains("with time zone") || data_type.ends_with("tz");
+        match data_type.split(['(', ' ']).next().unwrap_or_default() {
+            "tinyint" | "smallint" | "mediumint" | "int" | "integer" | "bigint" | "int2"
+            | "int4" | "int8" | "smallserial" | "serial" | "bigserial" | "year" => "integer",
+            "real" | "float" | "double" | "float4" | "float8" | "numeric" | "decimal" | "dec" => {
+                "number"
+            }
+            "date" => "date",
+            "time" if !with_time_zone => "time",
+            "datetime" | "timestamp" if !with_time_zone => "datetime",
+            "json" | "jsonb" => "json",
+            "blob" | "tinyblob" | "mediumblob" | "longblob" | "bytea" | "binary" | "varbinary" => {
+                "blob"
+            }
+            "mediumtext" | "longtext" | "clob" | "xml" => "textarea",
+            _ => "text",
+        }
+    }
+
+    /// Whether the column holds strings: a character, text, enum or set type.
+    pub fn is_string(&self) -> bool {
+        let data_type = self.data_type.to_lowercase();
+        ["char", "text", "clob"]
+            .iter()
+            .any(|t| data_type.contains(t))
+            || data_type.starts_with("enum")
+            || data_type.starts_with("set")
+    }
+
+    /// Typed value for text submitted through the column's widget. Only
+    /// string types have an empty value, elsewhere empty input means NULL.
+    /// Input that doesn't parse is passed on as text for the database to
+    /// accept or reject.
+    pub fn parse_input(&self, input: &str) -> CellValue {
+        let kind = self.input_kind();
+        let data_type = self.data_type.to_lowercase();
+        let is_string = data_type.is_empty() || self.is_string();
+        if input.is_empty() && kind != "boolean" && !is_string {
+            return CellValue::Null;
+        }
+        match kind {
+            "boolean" => match input {
+                "" | "false" | "0" | "off" => CellValue::Bool(false),
+                "true" | "1" | "on" => CellValue::Bool(true),
+                _ => CellValue::Text(input.to_string()),
+            },
+            "integer" => input
+                .parse()
+                .map(CellValue::Int)
+                .unwrap_or_else(|_| CellValue::Text(input.to_string())),
+            // Decimals keep their text form so no precision is lost.
+            "number" if !data_type.starts_with("numeric") && !data_type.starts_with("dec") => input
+                .parse()
+                .map(CellValue::Float)
+                .unwrap_or_else(|_| CellValue::Text(input.to_string())),
+            "datetime" => CellValue::DateTime(input.replacen('T', " ", 1)),
+            "date" | "time" => CellValue::DateTime(input.to_string()),
+            _ => CellValue::Text(input.to_string()),
+        }
+    }
+
+    /// Whether the column's widget can show `value` as read from the
+    /// database. Values a typed input can't hold, such as text stored in a
+    /// SQLite integer column, are edited as plain text instead.
+    pub fn input_accepts(&self, value: &str) -> bool {
+        let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+        let date = |s: &str| {
+            let parts: Vec<&str> = s.split('-').collect();
+            parts.len() == 3 && parts.iter().all(|p| digits(p))
+        };
+        let time = |s: &str| {
+            let (clock, fraction) = s.split_once('.').unwrap_or((s, "0"));
+            let parts: Vec<&str> = clock.split(':').collect();
+            (2..=3).contains(&parts.len()) && parts.iter().all(|p| digits(p)) && digits(fraction)
+        };
+        match self.input_kind() {
+            _ if value.is_empty() => true,
+            "boolean" => matches!(value, "true" | "false"),
+            "integer" => value.parse::<i64>().is_ok(),
+            "number" => value.parse::<f64>().is_ok(),
+            "date" => date(value),
+            "time" => time(value),
+            "datetime" => value
+                .split_once([' ', 'T'])
+                .is_some_and(|(d, t)| date(d) && time(t)),
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IndexInfo {
+    pub name: String,
+    pub unique: bool,
+    pub primary: bool,
+    /// Created for a PRIMARY KEY or UNIQUE constraint, and dropped with it.
+    pub from_constraint: bool,
+    /// Access method, e.g. BTREE, HASH or GIN.
+    pub method: String,
+    /// Key parts in index order.
+    pub columns: Vec<IndexColumn>,
+    /// WHERE clause of a partial index.
+    pub predicate: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ForeignKeyInfo {
+    /// Constraint name, empty on SQLite where constraints are unnamed.
+    pub name: String,
+    /// Table the key is declared on.
+    pub table: String,
+    pub columns: Vec<String>,
+    pub referenced_table: String,
+    /// Schema of the referenced table when it isn't the key's own. Only
+    /// Postgres has schemas.
+    pub referenced_schema: Option<String>,
+    /// Referenced columns, paired with `columns` by position.
+    pub referenced_columns: Vec<String>,
+    pub on_update: String,
+    pub on_delete: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct TriggerInfo {
+    pub name: String,
+    pub table: String,
+    /// BEFORE, AFTER or INSTEAD OF.
+    pub timing: String,
+    /// Firing events, e.g. INSERT or "INSERT OR UPDATE".
+    pub event: String,
+    /// The CREATE TRIGGER statement.
+    pub definition: String,
+}
+
+/// One key part of an index: a plain column or an expression.
+#[derive(Debug, Clone)]
+pub struct IndexColumn {
+    pub name: Option<String>,
+    pub expression: Option<String>,
+    pub descending: bool,
+}
+
+impl IndexInfo {
+    /// Whether dropping `column` drops this index too: it is keyed on that
+    /// column alone, or on PostgreSQL uses it anywhere.
+    pub fn dropped_with(&self, column: &str, database_type: &DatabaseType) -> bool {
+        let uses = |part: &IndexColumn| match (&part.name, &part.expression) {
+            (Some(name), _) => name == column,
+            (None, Some(expression)) => {
+                !statement::column_references(expression, column).is_empty()
+            }
+            (None, None) => false,
+        };
+        match database_type {
+            DatabaseType::Postgres => self.columns.iter().any(uses),
+            DatabaseType::Sqlite | DatabaseType::Mysql => {
+                self.predicate.is_none() && self.columns.len() == 1 && uses(&self.columns[0])
+            }
+        }
+    }
+}
+
+impl IndexColumn {
+    /// The key part as it would appear in CREATE INDEX.
+    pub fn definition(&self) -> String {
+        let term = match (&self.name, &self.expression) {
+            (Some(name), _) => name.clone(),
+            (None, Some(expression)) => expression.clone(),
+            (None, None) => "?".to_string(),
+        };
+        if self.descending {
+            format!("{} DESC", term)
+        } else {
+            term
+        }
+    }
+}
+
+/// Connection handed out by `DatabaseManager::acquire`.
+enum SchemaConnection {
+    Pooled(PoolConnection<Any>),
+    Detached(AnyConnection),
+}
+
+impl std::ops::Deref for SchemaConnection {
+    type Target = AnyConnection;
+
+    fn deref(&self) -> &AnyConnection {
+        match self {
+            SchemaConnection::Pooled(conn) => conn,
+            SchemaConnection::Detached(conn) => conn,
+        }
+    }
+}
+
+impl std::ops::DerefMut for SchemaConnection {
+    fn deref_mut(&mut self) -> &mut AnyConnection {
+        match self {
+            SchemaConnection::Pooled(conn) => conn,
+            SchemaConnection::Detached(conn) => conn,
+        }
+    }
+}
+
+/// Outcome of `DatabaseManager::update_row`.
+#[derive(Debug, Clone)]
+pub enum RowUpdate {
+    Updated,
+    /// The row changed since the form was loaded; holds its current values.
+    Conflict(Vec<(String, CellValue)>),
+    /// No row matches the key any more.
+    Missing,
+}
+
+/// How individual rows of a table are addressed for edits and deletes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RowIdentity {
+    PrimaryKey(Vec<String>),
+    /// Columns of a unique index, all NOT NULL.
+    UniqueKey(Vec<String>),
+    /// SQLite `rowid` or Postgres `ctid`.
+    RowId,
+    /// The comparable columns of the whole row. Identical rows can't be told
+    /// apart, so statements are limited to one row.
+    FullRow(Vec<String>),
+    Unsupported,
+}
+
+impl RowIdentity {
+    /// Names of the key fields identifying a row, empty when unsupported.
+    pub fn key_columns(&self, database_type: &DatabaseType) -> Vec<String> {
+        match self {
+            RowIdentity::PrimaryKey(columns)
+            | RowIdentity::UniqueKey(columns)
+            | RowIdentity::FullRow(columns) => columns.clone(),
+            RowIdentity::RowId => database_type
+                .row_id_column()
+                .map(|(name, _, _)| vec![name.to_string()])
+                .unwrap_or_default(),
+            RowIdentity::Unsupported => vec![],
+        }
+    }
+
+    /// Conditions matching the row a key from the content grid identifies.
+    /// Every key field must be given and nothing else; only a full-row match
+    /// leaves out the columns that are NULL. `None` when the key doesn't fit.
+    pub fn conditions(
+        &self,
+        database_type: &DatabaseType,
+        key: &HashMap<String, String>,
+    ) -> Option<HashMap<String, Option<String>>> {
+        let key_columns = self.key_columns(database_type);
+        if key_columns.is_empty() || key.keys().any(|k| !key_columns.contains(k)) {
+            return None;
+        }
+        let partial = matches!(self, RowIdentity::FullRow(_));
+        if !partial && key.len() != key_columns.len() {
+            return None;
+        }
+        Some(
+            key_columns
+                .into_iter()
+                .map(|column| {
+                    let value = key.get(&column).cloned();
+                    (column, value)
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Name for the copy of index `index` on table `table` when the table is
+/// duplicated as `new`: the table's name is swapped where it forms a whole
+/// `_`-separated part of the index name, otherwise the new name is prefixed.
+fn copied_index_name(index: &str, table: &str, new: &str) -> String {
+    if let Some(rest) = index
+        .strip_prefix(table)
+        .filter(|rest| rest.starts_with('_'))
+    {
+        format!("{}{}", new, rest)
+    } else if let Some(head) = index.strip_suffix(table).filter(|head| head.ends_with('_')) {
+        format!("{}{}", head, new)
+    } else if let Some(at) = index.find(&format!("_{}_", table)) {
+        format!("{}_{}{}", &index[..at], new, &index[at + table.len() + 1..])
+    } else {
+        format!("{}_{}", new, index)
+    }
+}
+
+/// `name`, or the first of `name1`, `name2`, ... not in `taken`.
+fn free_name(name: &str, taken: &[String]) -> String {
+    let is_taken = |candidate: &str| taken.iter().any(|t| t.eq_ignore_ascii_case(candidate));
+    if !is_taken(name) {
+        return name.to_string();
+    }
+    (1..)
+        .map(|n| format!("{}{}", name, n))
+        .find(|candidate| !is_taken(candidate))
+        .unwrap_or_default()
+}
+
+/// Rows to skip to reach the 1-based `page`, or `None` when the page or the
+/// row after it lies beyond what can be addressed.
+pub fn page_offset(page: usize, per_page: usize) -> Option<usize> {
+    let offset = page.checked_sub(1)?.checked_mul(per_page)?;
+    offset.checked_add(per_page)?.checked_add(1)?;
+    Some(offset)
+}
+
+/// Whether SQLite refused an ALTER TABLE because it can't make the change in
+/// place: the syntax predates its SQLite version, or the column is a key, is
+/// indexed or is used elsewhere in the schema. Other failures, such as an
+/// unknown column or a locked database, are the user's to see.
+fn sqlite_alter_unsupported(error: &sqlx::Error) -> bool {
+    let sqlx::Error::Database(error) = error else {
+        return false;
+    };
+    let message = error.message();
+    message.contains("syntax error")
+        || message.starts_with("cannot drop PRIMARY KEY column")
+        || message.starts_with("cannot drop UNIQUE column")
+        || message.contains("after drop column:")
+}
+
+/// An index term without its trailing ASC/DESC, which is reported separately.
+fn strip_sort_order(term: &str) -> String {
+    let upper = term.to_uppercase();
+    for suffix in [" ASC", " DESC"] {
+        if upper.ends_with(suffix) {
+            return term[..term.len() - suffix.len()].trim_end().to_string();
+        }
+    }
+    term.to_string()
+}
+
+fn query_result(rows: Vec<AnyRow>) -> QueryResult {
+    let mut result_rows = Vec::new();
+    let mut columns = Vec::new();
+
+    if let Some(first_row) = rows.first() {
+        columns = first_row
+            .columns()
+            .iter()
+            .map(|col| col.name().to_string())
+            .collect();
+    }
+
+    for row in rows {
+        let mut row_data = Vec::new();
+        for (i, _column) in row.columns().iter().enumerate() {
+            row_data.push(decode_cell(&row, i));
+        }
+        result_rows.push(row_data);
+    }
+
+    QueryResult {
+        columns,
+        rows: result_rows,
+        rows_affected: None,
+        last_insert_id: None,
+    }
+}
+
+/// Decode a cell from the value kinds the Any driver exposes.
+pub fn decode_cell(row: &AnyRow, index: usize) -> CellValue {
+    // The Any driver only exposes a handful of value kinds, identified by name.
+    let kind = match row.try_get_raw(index) {
+        Ok(value) if !value.is_null() => value.type_info().name().to_string(),
+        _ => return CellValue::Null,
+    };
+
+    let value = match kind.as_str() {
+        "BOOLEAN" => row.try_get_unchecked(index).ok().map(CellValue::Bool),
+        "SMALLINT" | "INTEGER" | "BIGINT" => row.try_get_unchecked(index).ok().map(CellValue::Int),
+        "REAL" | "DOUBLE" => row.try_get_unchecked(index).ok().map(CellValue::Float),
+        "TEXT" => row.try_get_unchecked(index).ok().map(CellValue::Text),
+        "BLOB" => row.try_get_unchecked(index).ok().map(CellValue::Bytes),
+        _ => None,
+    };
+
+    value.unwrap_or(CellValue::Null)
+}
+
+/// Outcome of one statement of a script.
+#[derive(Debug)]
+pub struct StatementResult {
+    pub sql: String,
+    pub result: Result<QueryResult, sqlx::Error>,
+    pub elapsed: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<CellValue>>,
+    pub rows_affected: Option<u64>,
+    pub last_insert_id: Option<i64>,
+}
+
+impl QueryResult {
+    /// Refine cells of columns belonging to `table_info` by their declared type.
+    pub fn with_column_types(mut self, table_info: &TableInfo) -> Self {
+        let types: Vec<Option<&str>> = self
+            .columns
+            .iter()
+            .map(|name| {
+                table_info
+                    .columns
+                    .iter()
+                    .find(|c| &c.name == name)
+                    .map(|c| c.data_type.as_str())
+            })
+            .collect();
+
+        for row in &mut self.rows {
+            for (cell, data_type) in row.iter_mut().zip(&types) {
+                if let Some(data_type) = data_type {
+                    *cell = std::mem::replace(cell, CellValue::Null).refine(data_type);
+                }
+            }
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copied_index_names_swap_whole_table_parts() {
+        assert_eq!(copied_index_name("t_name_idx", "t", "t2"), "t2_name_idx");
+        assert_eq!(copied_index_name("idx_t", "t", "t2"), "idx_t2");
+        assert_eq!(copied_index_name("ix_t_name", "t", "t2"), "ix_t2_name");
+        // "t" inside another word isn't the table.
+        assert_eq!(
+            copied_index_name("items_total", "t", "t2"),
+            "t2_items_total"
+        );
+    }
+
+    #[test]
+    fn free_name_appends_a_number() {
+        let taken = ["t2_id_seq".to_string(), "T2_ID_SEQ1".to_string()];
+        assert_eq!(free_name("t2_name_seq", &taken), "t2_name_seq");
+        assert_eq!(free_name("t2_id_seq", &taken), "t2_id_seq2");
+    }
 }
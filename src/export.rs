@@ -0,0 +1,282 @@
+use crate::config::{decode_cell, ColumnInfo, DatabaseType};
+use crate::models::{CellValue, ExportRequest, QualifiedName};
+use crate::query_builder;
+use futures::stream::{BoxStream, StreamExt};
+use rocket::http::{ContentType, Header};
+use rocket::response::stream::{stream, ByteStream};
+use rocket::response::{self, Responder};
+use rocket::Request;
+use sqlx::AnyPool;
+
+/// Number of rows buffered before a chunk is flushed to the client.
+const EXPORT_CHUNK_ROWS: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Sql,
+}
+
+impl ExportFormat {
+    pub fn parse(format: &str) -> Option<Self> {
+        match format.to_lowercase().as_str() {
+            "json" => Some(ExportFormat::Json),
+            "csv" => Some(ExportFormat::Csv),
+            "sql" => Some(ExportFormat::Sql),
+            _ => None,
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Sql => "sql",
+        }
+    }
+
+    fn content_type(&self) -> ContentType {
+        match self {
+            ExportFormat::Json => ContentType::JSON,
+            ExportFormat::Csv => ContentType::CSV,
+            ExportFormat::Sql => ContentType::new("application", "sql"),
+        }
+    }
+}
+
+/// A streamed file download with a `Content-Disposition: attachment` header.
+pub struct ExportDownload {
+    filename: String,
+    content_type: ContentType,
+    body: BoxStream<'static, Vec<u8>>,
+}
+
+impl<'r> Responder<'r, 'r> for ExportDownload {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'r> {
+        let mut response = ByteStream(self.body).respond_to(request)?;
+        response.set_header(self.content_type);
+        response.set_header(Header::new(
+            "Content-Disposition",
+            format!("attachment; filename=\"{}\"", self.filename),
+        ));
+        Ok(response)
+    }
+}
+
+fn csv_field(value: &CellValue) -> String {
+    match value {
+        CellValue::Null => String::new(),
+        other => other.to_form_value().unwrap_or_default(),
+    }
+}
+
+fn sql_literal(value: &CellValue, database_type: &DatabaseType) -> String {
+    match value {
+        CellValue::Null => "NULL".to_string(),
+        CellValue::Bool(b) => match database_type {
+            DatabaseType::Sqlite => (if *b { "1" } else { "0" }).to_string(),
+            _ => (if *b { "TRUE" } else { "FALSE" }).to_string(),
+        },
+        CellValue::Int(i) => i.to_string(),
+        CellValue::Float(f) if f.is_finite() => f.to_string(),
+        CellValue::Float(f) => {
+            let text = if f.is_nan() {
+                "NaN"
+            } else if f.is_sign_positive() {
+                "Infinity"
+            } else {
+                "-Infinity"
+            };
+            format!("'{}'", text)
+        }
+        CellValue::Text(s) | CellValue::DateTime(s) => database_type.quote_literal(s),
+        CellValue::Json(v) => database_type.quote_literal(&v.to_string()),
+        CellValue::Bytes(b) => {
+            let hex: String = b.iter().map(|byte| format!("{:02x}", byte)).collect();
+            match database_type {
+                DatabaseType::Postgres => format!("decode('{}', 'hex')", hex),
+                _ => format!("X'{}'", hex),
+            }
+        }
+    }
+}
+
+/// Build the SELECT statement backing an export. `columns` must already be
+/// validated against the table definition; the WHERE clause is used as raw
+/// SQL.
+pub fn build_export_sql(
+    request: &ExportRequest,
+    columns: &[ColumnInfo],
+    database_type: &DatabaseType,
+) -> String {
+    let mut sql = format!(
+        "SELECT {} FROM {}",
+        query_builder::select_list(database_type, columns),
+        database_type.quote_qualified(&request.table)
+    );
+
+    if let Some(ref where_clause) = request.where_clause
+        && !where_clause.trim().is_empty()
+    {
+        sql += &format!(" WHERE {}", where_clause.trim());
+    }
+
+    // On a line of its own, so a trailing `--` comment in the WHERE clause
+    // can't swallow it.
+    if let Some(limit) = request.limit {
+        sql += &format!("\nLIMIT {}", limit);
+    }
+
+    sql
+}
+
+/// Stream the rows of `sql` to the client in the requested format, flushing
+/// every `EXPORT_CHUNK_ROWS` rows so large tables are never held in memory.
+pub fn export_download(
+    pool: AnyPool,
+    sql: String,
+    table: QualifiedName,
+    columns: Vec<ColumnInfo>,
+    format: ExportFormat,
+    database_type: DatabaseType,
+) -> ExportDownload {
+    let filename = format!(
+        "{}.{}",
+        table.name.replace(['"', '/', '\\'], "_"),
+        format.extension()
+    );
+    let content_type = format.content_type();
+
+    let body = stream! {
+        let names: Vec<String> = columns.iter().map(|c| c.name.clone()).collect();
+        let mut rows = sqlx::query(&sql).fetch(&pool);
+        let mut chunk = export_header(format, &table.name, &names);
+        let mut buffered = 0;
+        let mut written = 0;
+
+        while let Some(row) = rows.next().await {
+            let row = match row {
+                Ok(row) => row,
+                Err(e) => {
+                    log::error!("Export of table {} aborted: {}", table.name, e);
+                    chunk.extend(export_error(&e.to_string()));
+                    yield chunk;
+                    return;
+                }
+            };
+
+            let values: Vec<CellValue> = columns
+                .iter()
+                .enumerate()
+                .map(|(i, column)| decode_cell(&row, i).refine(&column.data_type))
+                .collect();
+            write_export_row(&mut chunk, format, &table, &names, &values, written, &database_type);
+
+            written += 1;
+            buffered += 1;
+            if buffered >= EXPORT_CHUNK_ROWS {
+                yield std::mem::take(&mut chunk);
+                buffered = 0;
+            }
+        }
+
+        chunk.extend(export_footer(format, written));
+        yield chunk;
+    }
+    .boxed();
+
+    ExportDownload {
+        filename,
+        content_type,
+        body,
+    }
+}
+
+fn export_header(format: ExportFormat, table_name: &str, columns: &[String]) -> Vec<u8> {
+    match format {
+        ExportFormat::Json => b"[".to_vec(),
+        ExportFormat::Csv => {
+            let mut buffer = Vec::new();
+            write_csv_record(&mut buffer, columns);
+            buffer
+        }
+        ExportFormat::Sql => format!("-- Data export of table {}\n", table_name).into_bytes(),
+    }
+}
+
+/// Marker ending an export that failed part way. It is written instead of
+/// the footer as a bare line, so the file is no longer valid JSON, fails to
+/// run as SQL and shows up as a stray CSV record.
+fn export_error(message: &str) -> Vec<u8> {
+    format!("\nExport aborted: {}\n", message.replace('\n', " ")).into_bytes()
+}
+
+fn export_footer(format: ExportFormat, written: usize) -> Vec<u8> {
+    match format {
+        ExportFormat::Json if written == 0 => b"]\n".to_vec(),
+        ExportFormat::Json => b"\n]\n".to_vec(),
+        ExportFormat::Csv | ExportFormat::Sql => Vec::new(),
+    }
+}
+
+fn write_export_row(
+    buffer: &mut Vec<u8>,
+    format: ExportFormat,
+    table: &QualifiedName,
+    columns: &[String],
+    values: &[CellValue],
+    index: usize,
+    database_type: &DatabaseType,
+) {
+    match format {
+        ExportFormat::Json => {
+            // Objects are written by hand so keys keep the table's column order.
+            let fields: Vec<String> = columns
+                .iter()
+                .zip(values)
+                .map(|(column, value)| {
+                    format!(
+                        "{}: {}",
+                        serde_json::Value::String(column.clone()),
+                        serde_json::to_string(value).unwrap_or_else(|_| "null".to_string())
+                    )
+                })
+                .collect();
+            let separator = if index == 0 { "\n  " } else { ",\n  " };
+            buffer.extend(format!("{}{{{}}}", separator, fields.join(", ")).into_bytes());
+        }
+        ExportFormat::Csv => {
+            let fields: Vec<String> = values.iter().map(csv_field).collect();
+            write_csv_record(buffer, &fields);
+        }
+        ExportFormat::Sql => {
+            let column_list = columns
+                .iter()
+                .map(|c| database_type.quote_identifier(c))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let value_list = values
+                .iter()
+                .map(|v| sql_literal(v, database_type))
+                .collect::<Vec<_>>()
+                .join(", ");
+            buffer.extend(
+                format!(
+                    "INSERT INTO {} ({}) VALUES ({});\n",
+                    database_type.quote_qualified(table),
+                    column_list,
+                    value_list
+                )
+                .into_bytes(),
+            );
+        }
+    }
+}
+
+fn write_csv_record(buffer: &mut Vec<u8>, fields: &[String]) {
+    let mut writer = csv::Writer::from_writer(buffer);
+    // Writing into an in-memory buffer cannot fail.
+    let _ = writer.write_record(fields);
+    let _ = writer.flush();
+}
@@ -0,0 +1,559 @@
+use crate::config::{ColumnInfo, DatabaseType};
+use crate::models::{CellValue, ImportRequest, ImportRowError, QualifiedName};
+use crate::query_builder::QueryBuilder;
+use sqlx::{Acquire, AnyPool};
+
+/// A single value parsed from the import source.
+#[derive(Debug, Clone)]
+pub enum ImportValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl ImportValue {
+    fn from_json(value: &serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => ImportValue::Null,
+            serde_json::Value::Bool(b) => ImportValue::Bool(*b),
+            serde_json::Value::Number(n) => match n.as_i64() {
+                Some(i) => ImportValue::Int(i),
+                None => n
+                    .as_f64()
+                    .map(ImportValue::Float)
+                    .unwrap_or_else(|| ImportValue::Text(n.to_string())),
+            },
+            serde_json::Value::String(s) => ImportValue::Text(s.clone()),
+            // Nested arrays and objects are stored as their JSON text.
+            other => ImportValue::Text(other.to_string()),
+        }
+    }
+
+    fn from_csv(field: &str) -> Self {
+        if field.is_empty() {
+            ImportValue::Null
+        } else {
+            ImportValue::Text(field.to_string())
+        }
+    }
+
+    /// Convert textual values to the target column's type where it is
+    /// unambiguous, so strict databases don't reject text parameters.
+    fn coerce(self, column: &ColumnInfo) -> Self {
+        let text = match self {
+            ImportValue::Text(ref text) => text.trim(),
+            // Typed JSON values bound to a string column are rejected by
+            // Postgres, so they go in as their text.
+            ImportValue::Bool(b) if column.is_string() => return ImportValue::Text(b.to_string()),
+            ImportValue::Int(i) if column.is_string() => return ImportValue::Text(i.to_string()),
+            ImportValue::Float(f) if column.is_string() => {
+                return ImportValue::Text(f.to_string());
+            }
+            other => return other,
+        };
+        let data_type = column.data_type.to_uppercase();
+
+        // Judged by the type's leading word, so POINT or INTERVAL aren't
+        // taken for integers.
+        match column.input_kind() {
+            "integer" => {
+                if let Ok(i) = text.parse::<i64>() {
+                    return ImportValue::Int(i);
+                }
+            }
+            "boolean" => match text.to_lowercase().as_str() {
+                "true" | "t" | "1" | "yes" => return ImportValue::Bool(true),
+                "false" | "f" | "0" | "no" => return ImportValue::Bool(false),
+                _ => {}
+            },
+            _ if ["REAL", "FLOA", "DOUB"]
+                .iter()
+                .any(|t| data_type.contains(t)) =>
+            {
+                if let Ok(f) = text.parse::<f64>() {
+                    return ImportValue::Float(f);
+                }
+            }
+            _ => {}
+        }
+
+        self
+    }
+
+    fn into_cell(self) -> CellValue {
+        match self {
+            ImportValue::Null => CellValue::Null,
+            ImportValue::Bool(b) => CellValue::Bool(b),
+            ImportValue::Int(i) => CellValue::Int(i),
+            ImportValue::Float(f) => CellValue::Float(f),
+            ImportValue::Text(s) => CellValue::Text(s),
+        }
+    }
+}
+
+/// Parsed import source. A `None` cell means the field was absent from that
+/// record, so the column default applies.
+pub struct ImportData {
+    pub fields: Vec<String>,
+    pub rows: Vec<Vec<Option<ImportValue>>>,
+}
+
+pub struct ImportOutcome {
+    pub imported: usize,
+    pub errors: Vec<ImportRowError>,
+    pub committed: bool,
+}
+
+pub fn parse_import(request: &ImportRequest) -> Result<ImportData, String> {
+    let data = request.data.trim_start_matches('\u{feff}');
+    if data.trim().is_empty() {
+        return Err("No data to import".to_string());
+    }
+
+    match request.format.to_lowercase().as_str() {
+        "json" => parse_json(data),
+        "csv" => parse_csv(data),
+        other => Err(format!("Unsupported import format: {}", other)),
+    }
+}
+
+fn parse_json(data: &str) -> Result<ImportData, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(data).map_err(|e| format!("Invalid JSON: {}", e))?;
+
+    let records = match value {
+        serde_json::Value::Array(records) => records,
+        _ => return Err("JSON data must be an array of objects".to_string()),
+    };
+
+    let mut fields: Vec<String> = Vec::new();
+    for (i, record) in records.iter().enumerate() {
+        let object = record
+            .as_object()
+            .ok_or_else(|| format!("Row {} is not a JSON object", i + 1))?;
+        for key in object.keys() {
+            if !fields.contains(key) {
+                fields.push(key.clone());
+            }
+        }
+    }
+
+    let rows = records
+        .iter()
+        .filter_map(|record| record.as_object())
+        .map(|object| {
+            fields
+                .iter()
+                .map(|field| object.get(field).map(ImportValue::from_json))
+                .collect()
+        })
+        .collect();
+
+    Ok(ImportData { fields, rows })
+}
+
+fn parse_csv(data: &str) -> Result<ImportData, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(false)
+        .from_reader(data.as_bytes());
+
+    let fields: Vec<String> = reader
+        .headers()
+        .map_err(|e| format!("Invalid CSV header: {}", e))?
+        .iter()
+        .map(|h| h.trim().to_string())
+        .collect();
+
+    let mut rows = Vec::new();
+    for (i, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| format!("Invalid CSV at row {}: {}", i + 1, e))?;
+        rows.push(
+            record
+                .iter()
+                .map(|field| Some(ImportValue::from_csv(field)))
+                .collect(),
+        );
+    }
+
+    Ok(ImportData { fields, rows })
+}
+
+/// Match an import field to a table column, preferring an exact match.
+pub fn match_column<'c>(field: &str, columns: &'c [ColumnInfo]) -> Option<&'c ColumnInfo> {
+    columns
+        .iter()
+        .find(|c| c.name == field)
+        .or_else(|| columns.iter().find(|c| c.name.eq_ignore_ascii_case(field)))
+}
+
+/// Pick a column type able to hold every value imported for field `index`.
+pub fn infer_column_type(data: &ImportData, index: usize, database_type: &DatabaseType) -> String {
+    let values = data
+        .rows
+        .iter()
+        .filter_map(|row| row.get(index).and_then(|v| v.as_ref()))
+        .filter(|v| !matches!(v, ImportValue::Null));
+
+    // CSV values arrive as text, so judge them by what they parse as.
+    let mut all_int = true;
+    let mut all_number = true;
+    let mut all_bool = true;
+    let mut any = false;
+    for value in values {
+        any = true;
+        let (is_int, is_number, is_bool) = match value {
+            ImportValue::Int(_) => (true, true, false),
+            ImportValue::Float(_) => (false, true, false),
+            ImportValue::Bool(_) => (false, false, true),
+            ImportValue::Text(text) => {
+                let text = text.trim();
+                (
+                    text.parse::<i64>().is_ok(),
+                    text.parse::<f64>().is_ok(),
+                    matches!(text.to_lowercase().as_str(), "true" | "false"),
+                )
+            }
+            ImportValue::Null => (true, true, true),
+        };
+        all_int &= is_int;
+        all_number &= is_number;
+        all_bool &= is_bool;
+    }
+
+    let data_type = if !any {
+        "TEXT"
+    } else if all_bool {
+        match database_type {
+            // SQLite has no boolean storage class; booleans are stored as 0/1.
+            DatabaseType::Sqlite => "INTEGER",
+            DatabaseType::Mysql | DatabaseType::Postgres => "BOOLEAN",
+        }
+    } else if all_int {
+        match database_type {
+            DatabaseType::Sqlite => "INTEGER",
+            DatabaseType::Mysql | DatabaseType::Postgres => "BIGINT",
+        }
+    } else if all_number {
+        match database_type {
+            DatabaseType::Sqlite => "REAL",
+            DatabaseType::Mysql => "DOUBLE",
+            DatabaseType::Postgres => "DOUBLE PRECISION",
+        }
+    } else {
+        "TEXT"
+    };
+
+    data_type.to_string()
+}
+
+/// `INSERT` for one import row, leaving out fields without a column and
+/// fields absent from the record. Values are bound through `QueryBuilder`,
+/// so Postgres casts them to the column's type.
+fn insert_row(
+    database_type: &DatabaseType,
+    table: &QualifiedName,
+    columns: &[Option<ColumnInfo>],
+    row: Vec<Option<ImportValue>>,
+) -> QueryBuilder {
+    let values: Vec<(&ColumnInfo, ImportValue)> = row
+        .into_iter()
+        .zip(columns)
+        .filter_map(|(value, column)| Some((column.as_ref()?, value?)))
+        .collect();
+
+    let mut builder = QueryBuilder::new(database_type);
+    builder.push("INSERT INTO ").push_table(table);
+    if values.is_empty() {
+        builder.push(" DEFAULT VALUES");
+        return builder;
+    }
+
+    builder.push(" (");
+    for (i, (column, _)) in values.iter().enumerate() {
+        if i > 0 {
+            builder.push(", ");
+        }
+        builder.push_identifier(&column.name);
+    }
+    builder.push(") VALUES (");
+    for (i, (column, value)) in values.into_iter().enumerate() {
+        if i > 0 {
+            builder.push(", ");
+        }
+        builder.push_value(value.coerce(column).into_cell(), &column.data_type);
+    }
+    builder.push(")");
+    builder
+}
+
+/// Insert every row inside a single transaction, after running
+/// `schema_changes` such as new columns in it. With `ignore_errors` each row
+/// runs in its own savepoint so a failure doesn't poison the rest; otherwise
+/// the first failure rolls the whole import back.
+pub async fn import_rows(
+    pool: &AnyPool,
+    database_type: &DatabaseType,
+    table: &QualifiedName,
+    columns: &[Option<ColumnInfo>],
+    data: ImportData,
+    schema_changes: &[String],
+    ignore_errors: bool,
+) -> Result<ImportOutcome, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    for sql in schema_changes {
+        sqlx::query(sql).execute(&mut *tx).await?;
+    }
+    let mut imported = 0;
+    let mut errors = Vec::new();
+
+    for (i, row) in data.rows.into_iter().enumerate() {
+        let builder = insert_row(database_type, table, columns, row);
+        let query = builder.build();
+
+        let result = if ignore_errors {
+            let mut savepoint = tx.begin().await?;
+            match query.execute(&mut *savepoint).await {
+                Ok(_) => savepoint.commit().await,
+                Err(e) => {
+                    savepoint.rollback().await?;
+                    Err(e)
+                }
+            }
+        } else {
+            query.execute(&mut *tx).await.map(|_| ())
+        };
+
+        match result {
+            Ok(()) => imported += 1,
+            Err(e) => {
+                errors.push(ImportRowError {
+                    row: i + 1,
+                    message: e.to_string(),
+                });
+                if !ignore_errors {
+                    tx.rollback().await?;
+                    return Ok(ImportOutcome {
+                        imported: 0,
+                        errors,
+                        committed: false,
+                    });
+                }
+            }
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok(ImportOutcome {
+        imported,
+        errors,
+        committed: true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(data_type: &str) -> ColumnInfo {
+        ColumnInfo::new("c", data_type)
+    }
+
+    fn text(value: &str) -> ImportValue {
+        ImportValue::Text(value.to_string())
+    }
+
+    #[test]
+    fn coerce_integers_by_leading_word() {
+        for data_type in [
+            "INTEGER",
+            "int(11) unsigned",
+            "bigint",
+            "SMALLINT",
+            "serial",
+        ] {
+            assert!(
+                matches!(
+                    text(" 42 ").coerce(&column(data_type)),
+                    ImportValue::Int(42)
+                ),
+                "{}",
+                data_type
+            );
+        }
+        for data_type in ["POINT", "interval", "text"] {
+            assert!(
+                matches!(text("42").coerce(&column(data_type)), ImportValue::Text(_)),
+                "{}",
+                data_type
+            );
+        }
+    }
+
+    #[test]
+    fn coerce_typed_values_into_string_columns() {
+        assert!(matches!(
+            ImportValue::Int(7).coerce(&column("character varying(20)")),
+            ImportValue::Text(t) if t == "7"
+        ));
+        assert!(matches!(
+            ImportValue::Bool(true).coerce(&column("TEXT")),
+            ImportValue::Text(t) if t == "true"
+        ));
+        assert!(matches!(
+            ImportValue::Float(2.5).coerce(&column("enum('a','b')")),
+            ImportValue::Text(t) if t == "2.5"
+        ));
+        assert!(matches!(
+            ImportValue::Int(7).coerce(&column("")),
+            ImportValue::Int(7)
+        ));
+    }
+
+    #[test]
+    fn coerce_booleans_and_floats() {
+        assert!(matches!(
+            text("yes").coerce(&column("BOOLEAN")),
+            ImportValue::Bool(true)
+        ));
+        assert!(matches!(
+            text("0").coerce(&column("tinyint(1)")),
+            ImportValue::Bool(false)
+        ));
+        assert!(matches!(
+            text("1.5").coerce(&column("DOUBLE PRECISION")),
+            ImportValue::Float(f) if f == 1.5
+        ));
+        assert!(matches!(
+            text("abc").coerce(&column("REAL")),
+            ImportValue::Text(_)
+        ));
+    }
+
+    fn request(format: &str, data: &str) -> ImportRequest {
+        ImportRequest {
+            table: QualifiedName::new(None, "t"),
+            format: format.to_string(),
+            data: data.to_string(),
+            create_columns: false,
+            ignore_errors: false,
+        }
+    }
+
+    #[test]
+    fn parse_json_collects_fields_across_records() {
+        let data = parse_import(&request(
+            "JSON",
+            "\u{feff}[{\"a\": 1, \"b\": null}, {\"c\": [1, 2], \"a\": 2.5}]",
+        ))
+        .unwrap();
+        assert_eq!(data.fields, ["a", "b", "c"]);
+        assert!(matches!(
+            data.rows[0].as_slice(),
+            [Some(ImportValue::Int(1)), Some(ImportValue::Null), None]
+        ));
+        assert!(matches!(
+            data.rows[1].as_slice(),
+            [Some(ImportValue::Float(_)), None, Some(ImportValue::Text(t))] if t == "[1,2]"
+        ));
+
+        assert!(parse_import(&request("json", "{\"a\": 1}")).is_err());
+        assert!(parse_import(&request("json", "[1]")).is_err());
+        assert!(parse_import(&request("xml", "<a/>")).is_err());
+        assert!(parse_import(&request("csv", "  \n")).is_err());
+    }
+
+    #[test]
+    fn parse_csv_reads_empty_fields_as_null() {
+        let data = parse_import(&request("csv", " id ,name\n1,\"a, b\"\n2,\n")).unwrap();
+        assert_eq!(data.fields, ["id", "name"]);
+        assert!(matches!(
+            data.rows[0].as_slice(),
+            [Some(ImportValue::Text(id)), Some(ImportValue::Text(name))]
+                if id == "1" && name == "a, b"
+        ));
+        assert!(matches!(data.rows[1][1], Some(ImportValue::Null)));
+
+        assert!(parse_import(&request("csv", "a,b\n1\n")).is_err());
+    }
+
+    #[test]
+    fn match_column_prefers_exact_names() {
+        let columns = [
+            ColumnInfo::new("Name", "TEXT"),
+            ColumnInfo::new("name", "TEXT"),
+        ];
+        assert!(std::ptr::eq(
+            match_column("name", &columns).unwrap(),
+            &columns[1]
+        ));
+        assert!(std::ptr::eq(
+            match_column("NAME", &columns).unwrap(),
+            &columns[0]
+        ));
+        assert!(match_column("id", &columns).is_none());
+    }
+
+    #[test]
+    fn infer_column_type_from_values() {
+        let data = parse_import(&request(
+            "csv",
+            "int,real,bool,text,empty\n1,1,true,1,\n-2,2.5,FALSE,x,\n",
+        ))
+        .unwrap();
+        let inferred = |database_type| {
+            (0..data.fields.len())
+                .map(|i| infer_column_type(&data, i, &database_type))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            inferred(DatabaseType::Postgres),
+            ["BIGINT", "DOUBLE PRECISION", "BOOLEAN", "TEXT", "TEXT"]
+        );
+        assert_eq!(
+            inferred(DatabaseType::Sqlite),
+            ["INTEGER", "REAL", "INTEGER", "TEXT", "TEXT"]
+        );
+    }
+
+    #[test]
+    fn insert_row_casts_values_on_postgres() {
+        let columns = [
+            Some(ColumnInfo::new("id", "integer")),
+            None,
+            Some(ColumnInfo::new("at", "timestamp(3) with time zone")),
+            Some(ColumnInfo::new("note", "text")),
+        ];
+        let row = vec![
+            Some(text("1")),
+            Some(text("ignored")),
+            Some(text("2024-01-02 03:04:05+00")),
+            None,
+        ];
+        let table = QualifiedName::new(Some("s2".to_string()), "events");
+
+        let builder = insert_row(&DatabaseType::Postgres, &table, &columns, row.clone());
+        assert_eq!(
+            builder.sql(),
+            "INSERT INTO \"s2\".\"events\" (\"id\", \"at\") \
+             VALUES (CAST($1 AS integer), CAST($2 AS timestamp with time zone))"
+        );
+        assert_eq!(
+            builder.params(),
+            [
+                CellValue::Int(1),
+                CellValue::Text("2024-01-02 03:04:05+00".to_string())
+            ]
+        );
+
+        let builder = insert_row(&DatabaseType::Sqlite, &table, &columns, row);
+        assert_eq!(
+            builder.sql(),
+            "INSERT INTO \"s2\".\"events\" (\"id\", \"at\") VALUES (?, ?)"
+        );
+        let builder = insert_row(&DatabaseType::Mysql, &table, &columns, vec![None]);
+        assert_eq!(builder.sql(), "INSERT INTO `s2`.`events` DEFAULT VALUES");
+    }
+}
@@ -1,10 +1,15 @@
 use clap::Parser;
+use rocket::data::{Limits, ToByteUnit};
 use rocket::fs::FileServer;
 use rocket::routes;
 
 mod config;
+mod export;
+mod import;
 mod models;
+mod query_builder;
 mod routes;
+mod statement;
 mod template;
 
 use config::{DatabaseConfig, DatabasePool};
@@ -44,10 +49,11 @@ pub struct Args {
 }
 
 #[rocket::main]
-async fn main() -> Result<(), rocket::Error> {
+async fn main() -> Result<(), Box<rocket::Error>> {
     let args = Args::parse();
 
-    let db_config = DatabaseConfig::from_url(&args.database_url).expect("Invalid database URL");
+    let mut db_config = DatabaseConfig::from_url(&args.database_url).expect("Invalid database URL");
+    db_config.readonly |= args.readonly;
 
     let host = args.host.clone();
     let port = args.port;
@@ -57,7 +63,15 @@ async fn main() -> Result<(), rocket::Error> {
         // use yourself, so security is considered behind the functions.
         .merge(("secret_key", "h/ie6GKkDtaurjNrQYCRsrSaWLNRVA2hSeyMSD8NycZphe7Le6ZZiJsdareCfE3jIuMV9hG/nbxRCJNKhUBkuw=="))
         .merge(("address", host))
-        .merge(("port", port));
+        .merge(("port", port))
+        // Imports upload whole files or pasted data through a multipart form.
+        .merge((
+            "limits",
+            Limits::default()
+                .limit("data-form", 64.mebibytes())
+                .limit("file", 64.mebibytes())
+                .limit("string", 64.mebibytes()),
+        ));
 
     let _res = rocket::custom(figment)
         .manage(args)
@@ -79,24 +93,49 @@ async fn main() -> Result<(), rocket::Error> {
                 tables::table_query,
                 tables::table_query_execute,
                 tables::table_insert,
+                tables::table_insert_execute,
                 tables::table_update,
                 tables::table_update_execute,
+                tables::table_delete_execute,
                 tables::table_export,
+                tables::table_export_execute,
                 tables::table_import,
+                tables::table_import_execute,
+                table_admin::create_table,
+                table_admin::create_table_execute,
+                table_admin::rename_table,
+                table_admin::rename_table_execute,
+                table_admin::duplicate_table,
+                table_admin::duplicate_table_execute,
+                table_admin::truncate_table_execute,
+                table_admin::drop_table_execute,
                 indexes::add_index,
                 indexes::add_index_execute,
                 indexes::drop_index,
                 indexes::drop_index_execute,
+                triggers::trigger,
+                triggers::create_trigger,
+                triggers::create_trigger_execute,
+                triggers::drop_trigger_execute,
+                views::view,
+                views::create_view,
+                views::edit_view,
+                views::create_view_execute,
+                views::drop_view_execute,
+                views::refresh_view_execute,
                 columns::add_column,
                 columns::add_column_execute,
                 columns::drop_column,
                 columns::drop_column_execute,
                 columns::rename_column,
                 columns::rename_column_execute,
+                columns::modify_column,
+                columns::modify_column_execute,
             ],
         )
         .mount("/static", FileServer::from("static"))
         .attach(DatabasePool::init())
+        .attach(schema::SchemaPrefix)
         .ignite()
         .await?
         .launch()
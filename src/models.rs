@@ -1,11 +1,170 @@
-use serde::{Deserialize, Serialize};
+use base64::Engine;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashMap;
+use std::fmt;
+
+/// A single decoded cell. Serializes to the matching native JSON type; bytes
+/// are emitted as base64.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+    Bytes(Vec<u8>),
+    Json(serde_json::Value),
+    // Date, time or timestamp in the database's own text form.
+    DateTime(String),
+}
+
+impl CellValue {
+    pub fn is_null(&self) -> bool {
+        matches!(self, CellValue::Null)
+    }
+
+    /// Short name of the variant, for styling cells by kind.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CellValue::Null => "null",
+            CellValue::Bool(_) => "bool",
+            CellValue::Int(_) | CellValue::Float(_) => "number",
+            CellValue::Text(_) => "text",
+            CellValue::Bytes(_) => "bytes",
+            CellValue::Json(_) => "json",
+            CellValue::DateTime(_) => "datetime",
+        }
+    }
+
+    /// Text for form fields and key values; `None` for NULL.
+    pub fn to_form_value(&self) -> Option<String> {
+        match self {
+            CellValue::Null => None,
+            CellValue::Bytes(b) => Some(base64::engine::general_purpose::STANDARD.encode(b)),
+            other => Some(other.to_string()),
+        }
+    }
+
+    /// Text matching the value in a row key on every backend, with booleans
+    /// as 1 and 0; `None` for NULL.
+    pub fn to_key_value(&self) -> Option<String> {
+        match self {
+            CellValue::Bool(b) => Some(if *b { "1" } else { "0" }.to_string()),
+            other => other.to_form_value(),
+        }
+    }
+
+    /// Reinterpret a value decoded from the wire using the column's declared
+    /// type, recovering booleans, JSON, dates and text stored as bytes.
+    pub fn refine(self, data_type: &str) -> Self {
+        let data_type = data_type.to_lowercase();
+
+        if data_type.starts_with("bool") || data_type == "tinyint(1)" {
+            return match self {
+                CellValue::Int(i) => CellValue::Bool(i != 0),
+                CellValue::Text(ref s) => match s.as_str() {
+                    "true" | "t" | "1" => CellValue::Bool(true),
+                    "false" | "f" | "0" => CellValue::Bool(false),
+                    _ => self,
+                },
+                other => other,
+            };
+        }
+
+        match self {
+            CellValue::Text(s) if data_type.starts_with("json") => serde_json::from_str(&s)
+                .map(CellValue::Json)
+                .unwrap_or(CellValue::Text(s)),
+            CellValue::Text(s)
+                if ["date", "time", "year"]
+                    .iter()
+                    .any(|t| data_type.contains(t)) =>
+            {
+                CellValue::DateTime(s)
+            }
+            // MySQL reports TEXT columns as blobs.
+            CellValue::Bytes(b)
+                if ["text", "char", "clob"]
+                    .iter()
+                    .any(|t| data_type.contains(t)) =>
+            {
+                match String::from_utf8(b) {
+                    Ok(s) => CellValue::Text(s),
+                    Err(e) => CellValue::Bytes(e.into_bytes()),
+                }
+            }
+            other => other,
+        }
+    }
+
+    fn from_json(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => CellValue::Null,
+            serde_json::Value::Bool(b) => CellValue::Bool(b),
+            serde_json::Value::Number(ref n) => match (n.as_i64(), n.as_f64()) {
+                (Some(i), _) => CellValue::Int(i),
+                (None, Some(f)) => CellValue::Float(f),
+                _ => CellValue::Json(value),
+            },
+            serde_json::Value::String(s) => CellValue::Text(s),
+            other => CellValue::Json(other),
+        }
+    }
+}
+
+impl fmt::Display for CellValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CellValue::Null => write!(f, "NULL"),
+            CellValue::Bool(b) => write!(f, "{}", b),
+            CellValue::Int(i) => write!(f, "{}", i),
+            CellValue::Float(v) => write!(f, "{}", v),
+            CellValue::Text(s) | CellValue::DateTime(s) => write!(f, "{}", s),
+            CellValue::Bytes(b) => write!(f, "<{} bytes>", b.len()),
+            CellValue::Json(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+impl Serialize for CellValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            CellValue::Null => serializer.serialize_none(),
+            CellValue::Bool(b) => serializer.serialize_bool(*b),
+            CellValue::Int(i) => serializer.serialize_i64(*i),
+            CellValue::Float(v) => serializer.serialize_f64(*v),
+            CellValue::Text(s) | CellValue::DateTime(s) => serializer.serialize_str(s),
+            CellValue::Bytes(b) => {
+                serializer.serialize_str(&base64::engine::general_purpose::STANDARD.encode(b))
+            }
+            CellValue::Json(v) => v.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for CellValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        serde_json::Value::deserialize(deserializer).map(CellValue::from_json)
+    }
+}
+
+/// Fingerprint of a row's values. The edit form carries it so an update can
+/// tell whether the row changed since the form was loaded. It is a 64-bit
+/// FNV-1a hash of the values as JSON, which unlike the standard library's
+/// hasher stays the same across Rust releases.
+pub fn row_version(values: &[CellValue]) -> String {
+    let json = serde_json::to_string(values).unwrap_or_default();
+    let hash = json.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TableData {
     pub name: String,
     pub columns: Vec<String>,
-    pub rows: Vec<Vec<Option<String>>>,
+    pub rows: Vec<Vec<CellValue>>,
     pub total_rows: i64,
     pub page: usize,
     pub per_page: usize,
@@ -16,7 +175,7 @@ pub struct TableData {
 pub struct TableDataForTemplate {
     pub name: String,
     pub columns: Vec<String>,
-    pub rows: Vec<Vec<String>>,
+    pub rows: Vec<Vec<CellValue>>,
     pub total_rows: i64,
     pub page: usize,
     pub per_page: usize,
@@ -25,20 +184,10 @@ pub struct TableDataForTemplate {
 
 impl TableDataForTemplate {
     pub fn from_table_data(data: TableData) -> Self {
-        let processed_rows = data
-            .rows
-            .into_iter()
-            .map(|row| {
-                row.into_iter()
-                    .map(|cell| cell.unwrap_or_else(|| "NULL".to_string()))
-                    .collect()
-            })
-            .collect();
-
         Self {
             name: data.name,
             columns: data.columns,
-            rows: processed_rows,
+            rows: data.rows,
             total_rows: data.total_rows,
             page: data.page,
             per_page: data.per_page,
@@ -53,61 +202,65 @@ pub struct QueryRequest {
     pub page: Option<usize>,
     pub per_page: Option<usize>,
     pub ordering: Option<i32>,
+    // Count the total rows of SELECT results (capped); on by default.
+    pub count: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QueryResponse {
     pub columns: Vec<String>,
-    pub rows: Vec<Vec<Option<String>>>,
+    pub rows: Vec<Vec<CellValue>>,
     pub total_rows: Option<i64>,
     pub page: usize,
     pub per_page: usize,
     pub total_pages: usize,
     pub error: Option<String>,
     pub rows_affected: Option<u64>,
+    // Set for INSERT statements on SQLite and MySQL; Postgres has no equivalent.
+    pub last_insert_id: Option<i64>,
+    pub has_more: bool,
+    // Set when counting stopped at the cap, so `total_rows` and `total_pages`
+    // are lower bounds.
+    pub total_rows_capped: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QueryResponseForTemplate {
     pub columns: Vec<String>,
-    pub rows: Vec<Vec<String>>,
+    pub rows: Vec<Vec<CellValue>>,
     pub total_rows: Option<i64>,
     pub page: usize,
     pub per_page: usize,
     pub total_pages: usize,
     pub error: String,
     pub rows_affected_display: String,
+    pub last_insert_id_display: String,
 }
 
 impl QueryResponseForTemplate {
     #[allow(unused)]
     pub fn from_query_response(response: QueryResponse) -> Self {
-        let processed_rows = response
-            .rows
-            .into_iter()
-            .map(|row| {
-                row.into_iter()
-                    .map(|cell| cell.unwrap_or_else(|| "NULL".to_string()))
-                    .collect()
-            })
-            .collect();
-
         let rows_affected_display = match response.rows_affected {
             Some(count) => count.to_string(),
-            None => "0".to_string(),
+            None => String::new(),
+        };
+        let last_insert_id_display = match response.last_insert_id {
+            Some(id) => id.to_string(),
+            None => String::new(),
         };
 
-        let error = response.error.unwrap_or_else(|| String::new());
+        let error = response.error.unwrap_or_default();
 
         Self {
             columns: response.columns,
-            rows: processed_rows,
+            rows: response.rows,
             total_rows: response.total_rows,
             page: response.page,
             per_page: response.per_page,
             total_pages: response.total_pages,
             error,
             rows_affected_display,
+            last_insert_id_display,
         }
     }
 }
@@ -118,6 +271,8 @@ pub struct TableStructure {
     pub columns: Vec<ColumnDetail>,
     pub indexes: Vec<IndexDetail>,
     pub foreign_keys: Vec<ForeignKeyDetail>,
+    // Foreign keys of other tables referencing this one.
+    pub referenced_by: Vec<ForeignKeyDetail>,
     pub triggers: Vec<TriggerDetail>,
     pub create_sql: Option<String>,
 }
@@ -128,6 +283,7 @@ pub struct TableStructureForTemplate {
     pub columns: Vec<ColumnDetailForTemplate>,
     pub indexes: Vec<IndexDetail>,
     pub foreign_keys: Vec<ForeignKeyDetail>,
+    pub referenced_by: Vec<ForeignKeyDetail>,
     pub triggers: Vec<TriggerDetail>,
     pub create_sql: String,
 }
@@ -145,8 +301,9 @@ impl TableStructureForTemplate {
             columns: processed_columns,
             indexes: structure.indexes,
             foreign_keys: structure.foreign_keys,
+            referenced_by: structure.referenced_by,
             triggers: structure.triggers,
-            create_sql: structure.create_sql.unwrap_or_else(|| String::new()),
+            create_sql: structure.create_sql.unwrap_or_default(),
         }
     }
 }
@@ -159,7 +316,12 @@ pub struct ColumnDetail {
     pub default_value: Option<String>,
     pub is_primary_key: bool,
     pub is_auto_increment: bool,
-    pub max_length: Option<i32>,
+    pub max_length: Option<i64>,
+    pub numeric_precision: Option<i64>,
+    pub numeric_scale: Option<i64>,
+    pub collation: Option<String>,
+    pub generated: Option<String>,
+    pub comment: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -171,16 +333,26 @@ pub struct ColumnDetailForTemplate {
     pub has_default: bool,
     pub is_primary_key: bool,
     pub is_auto_increment: bool,
-    pub max_length: i32,
+    pub max_length: i64,
     pub has_max_length: bool,
+    // Precision and scale, e.g. `10, 2`; empty when not numeric.
+    pub precision: String,
+    pub collation: String,
+    pub generated: String,
+    pub comment: String,
 }
 
 impl ColumnDetailForTemplate {
     pub fn from_column_detail(detail: ColumnDetail) -> Self {
         let has_default = detail.default_value.is_some();
-        let default_value = detail.default_value.unwrap_or_else(|| String::new());
+        let default_value = detail.default_value.unwrap_or_default();
         let has_max_length = detail.max_length.is_some();
         let max_length = detail.max_length.unwrap_or(0);
+        let precision = match (detail.numeric_precision, detail.numeric_scale) {
+            (Some(precision), Some(scale)) => format!("{}, {}", precision, scale),
+            (Some(precision), None) => precision.to_string(),
+            _ => String::new(),
+        };
 
         Self {
             name: detail.name,
@@ -192,6 +364,10 @@ impl ColumnDetailForTemplate {
             is_auto_increment: detail.is_auto_increment,
             max_length,
             has_max_length,
+            precision,
+            collation: detail.collation.unwrap_or_default(),
+            generated: detail.generated.unwrap_or_default(),
+            comment: detail.comment.unwrap_or_default(),
         }
     }
 }
@@ -199,17 +375,24 @@ impl ColumnDetailForTemplate {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IndexDetail {
     pub name: String,
+    // Key parts in order: column names or expressions, with DESC if descending.
     pub columns: Vec<String>,
     pub unique: bool,
+    pub primary: bool,
+    // Belongs to a constraint, so it can't be dropped on its own.
+    pub from_constraint: bool,
     pub index_type: String,
+    // WHERE clause of a partial index.
+    pub predicate: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ForeignKeyDetail {
     pub name: String,
-    pub column: String,
+    pub table: String,
+    pub columns: Vec<String>,
     pub referenced_table: String,
-    pub referenced_column: String,
+    pub referenced_columns: Vec<String>,
     pub on_delete: String,
     pub on_update: String,
 }
@@ -223,28 +406,107 @@ pub struct TriggerDetail {
     pub definition: String,
 }
 
+/// A table or view name, qualified by its schema on Postgres. Without a
+/// schema the name resolves through the search_path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QualifiedName {
+    pub schema: Option<String>,
+    pub name: String,
+}
+
+impl QualifiedName {
+    pub fn new(schema: Option<String>, name: &str) -> Self {
+        Self {
+            schema,
+            name: name.to_string(),
+        }
+    }
+}
+
+/// Value written to a column by an insert or update.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ColumnValue {
+    Value(CellValue),
+    // The column's default, or NULL when it has none.
+    Default,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InsertRequest {
-    pub table: String,
-    pub data: HashMap<String, Option<String>>,
+    pub table: QualifiedName,
+    pub data: HashMap<String, ColumnValue>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdateRequest {
-    pub table: String,
-    pub data: HashMap<String, Option<String>>,
-    pub where_clause: HashMap<String, String>,
+    pub table: QualifiedName,
+    pub data: HashMap<String, ColumnValue>,
+    pub where_clause: HashMap<String, Option<String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeleteRequest {
-    pub table: String,
-    pub where_clause: HashMap<String, String>,
+    pub table: QualifiedName,
+    pub where_clause: HashMap<String, Option<String>>,
+}
+
+/// Condition on one column of the table content grid.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnFilter {
+    pub column: String,
+    pub operator: FilterOperator,
+    pub value: Option<String>,
+    // Upper bound of a range filter; `value` is the lower one.
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FilterOperator {
+    Equals,
+    Contains,
+    Range,
+    IsNull,
+    NotNull,
+}
+
+impl FilterOperator {
+    pub const ALL: [FilterOperator; 5] = [
+        FilterOperator::Equals,
+        FilterOperator::Contains,
+        FilterOperator::Range,
+        FilterOperator::IsNull,
+        FilterOperator::NotNull,
+    ];
+
+    /// Parse the operator name used in query strings.
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|op| op.as_str() == name)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FilterOperator::Equals => "eq",
+            FilterOperator::Contains => "contains",
+            FilterOperator::Range => "range",
+            FilterOperator::IsNull => "null",
+            FilterOperator::NotNull => "notnull",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FilterOperator::Equals => "equals",
+            FilterOperator::Contains => "contains",
+            FilterOperator::Range => "between",
+            FilterOperator::IsNull => "is NULL",
+            FilterOperator::NotNull => "is not NULL",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateTableRequest {
-    pub name: String,
+    pub name: QualifiedName,
     pub columns: Vec<CreateColumnRequest>,
 }
 
@@ -256,56 +518,92 @@ pub struct CreateColumnRequest {
     pub default_value: Option<String>,
     pub primary_key: bool,
     pub auto_increment: bool,
+    pub unique: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AddColumnRequest {
-    pub table: String,
+    pub table: QualifiedName,
     pub column: CreateColumnRequest,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DropColumnRequest {
-    pub table: String,
+    pub table: QualifiedName,
     pub column: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RenameColumnRequest {
-    pub table: String,
+    pub table: QualifiedName,
     pub old_name: String,
     pub new_name: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModifyColumnRequest {
+    pub table: QualifiedName,
+    pub column: String,
+    pub data_type: String,
+    pub nullable: bool,
+    pub default_value: Option<String>,
+}
+
+/// What a SQLite table rebuild does to one of the table's columns.
+#[derive(Debug, Clone)]
+pub enum ColumnChange {
+    Drop,
+    Rename(String),
+    // Replace the column's definition, keeping its name.
+    Modify(String),
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateIndexRequest {
-    pub table: String,
+    pub table: QualifiedName,
     pub name: String,
     pub columns: Vec<String>,
     pub unique: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateViewRequest {
+    pub name: QualifiedName,
+    pub query: String,
+    pub materialized: bool,
+    // Replace an existing view of the same name.
+    pub replace: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DropIndexRequest {
-    pub table: String,
+    pub table: QualifiedName,
     pub name: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExportRequest {
-    pub table: String,
-    pub format: String, // "json" or "csv"
+    pub table: QualifiedName,
+    pub format: String, // "json", "csv" or "sql"
     pub columns: Option<Vec<String>>,
+    // Raw SQL condition, without the WHERE keyword.
     pub where_clause: Option<String>,
     pub limit: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImportRequest {
-    pub table: String,
+    pub table: QualifiedName,
     pub format: String, // "json" or "csv"
     pub data: String,
     pub create_columns: bool,
+    pub ignore_errors: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportRowError {
+    pub row: usize,
+    pub message: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -364,12 +662,33 @@ pub struct DatabaseStats {
     pub created: Option<chrono::DateTime<chrono::Utc>>,
     pub modified: Option<chrono::DateTime<chrono::Utc>>,
     pub readonly: bool,
+    pub table_stats: Vec<TableStats>,
+    // SQLite storage settings, as (pragma, value) pairs.
+    pub pragmas: Vec<(String, String)>,
+}
+
+/// Size of a table as tracked by the server's statistics, which may lag
+/// behind the table itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableStats {
+    pub name: String,
+    pub row_estimate: Option<i64>,
+    pub size: Option<u64>,
+}
+
+impl TableStats {
+    pub fn size_display(&self) -> String {
+        self.size.map(format_file_size).unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ViewInfo {
     pub name: String,
+    // The view's query.
     pub definition: String,
+    // A Postgres materialized view.
+    pub materialized: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -397,32 +716,19 @@ pub fn format_file_size(size: u64) -> String {
     format!("{:.1} {}", value, UNITS[unit_index])
 }
 
-// Helper function to escape SQL identifiers
-pub fn escape_identifier(identifier: &str) -> String {
-    format!("\"{}\"", identifier.replace("\"", "\"\""))
-}
-
-// Helper function to escape SQL string literals
-pub fn escape_string_literal(value: &str) -> String {
-    format!("'{}'", value.replace("'", "''"))
-}
-
-// Helper function to build WHERE clause from HashMap
-pub fn build_where_clause(conditions: &HashMap<String, String>) -> String {
-    if conditions.is_empty() {
-        return String::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn row_version_is_stable() {
+        let row = [
+            CellValue::Int(1),
+            CellValue::Text("a".to_string()),
+            CellValue::Null,
+        ];
+        // Pinned, since versions outlive the process that issued them.
+        assert_eq!(row_version(&row), "0008a8266bfd485c");
+        assert_ne!(row_version(&row[..2]), row_version(&row));
     }
-
-    let clauses: Vec<String> = conditions
-        .iter()
-        .map(|(key, value)| {
-            format!(
-                "{} = {}",
-                escape_identifier(key),
-                escape_string_literal(value)
-            )
-        })
-        .collect();
-
-    format!("WHERE {}", clauses.join(" AND "))
 }
@@ -0,0 +1,567 @@
+use crate::config::{ColumnInfo, DatabaseType, TableInfo};
+use crate::models::{
+    CellValue, ColumnFilter, ColumnValue, FilterOperator, InsertRequest, QualifiedName,
+    UpdateRequest,
+};
+use sqlx::any::AnyArguments;
+use sqlx::query::Query;
+use sqlx::Any;
+use std::collections::HashMap;
+
+/// Builds a statement for one dialect: identifiers are quoted with the
+/// dialect's style and values are always passed as bound parameters.
+pub struct QueryBuilder {
+    database_type: DatabaseType,
+    sql: String,
+    params: Vec<CellValue>,
+}
+
+impl QueryBuilder {
+    pub fn new(database_type: &DatabaseType) -> Self {
+        Self {
+            database_type: database_type.clone(),
+            sql: String::new(),
+            params: Vec::new(),
+        }
+    }
+
+    /// Append raw SQL. Never pass user input here.
+    pub fn push(&mut self, sql: &str) -> &mut Self {
+        self.sql.push_str(sql);
+        self
+    }
+
+    pub fn push_identifier(&mut self, identifier: &str) -> &mut Self {
+        let quoted = self.database_type.quote_identifier(identifier);
+        self.push(&quoted)
+    }
+
+    pub fn push_table(&mut self, table: &QualifiedName) -> &mut Self {
+        let quoted = self.database_type.quote_qualified(table);
+        self.push(&quoted)
+    }
+
+    /// Append a placeholder bound to `value` as text, see `push_value`.
+    pub fn push_bind(&mut self, value: Option<String>, data_type: &str) -> &mut Self {
+        self.push_value(value.map_or(CellValue::Null, CellValue::Text), data_type)
+    }
+
+    /// Append a placeholder bound to `value`. Postgres won't convert text or
+    /// mismatched numbers implicitly, so there the placeholder is cast to the
+    /// column's `data_type`.
+    pub fn push_value(&mut self, value: CellValue, data_type: &str) -> &mut Self {
+        // A bound NULL arrives as a typed (integer) NULL on Postgres, which may
+        // not cast to the column's type, so NULL is written literally.
+        if value.is_null() {
+            return self.push("NULL");
+        }
+        self.params.push(value);
+        let placeholder = self.database_type.placeholder(self.params.len());
+        let sql = match postgres_cast_type(&self.database_type, data_type) {
+            Some(cast) => format!("CAST({} AS {})", placeholder, cast),
+            None => placeholder,
+        };
+        self.push(&sql)
+    }
+
+    /// Append `column = value`.
+    pub fn push_equals(&mut self, column: &str, value: String, data_type: &str) -> &mut Self {
+        self.push_identifier(column).push(" = ");
+        self.push_bind(Some(value), data_type)
+    }
+
+    /// Append a WHERE clause matching every column of `conditions`, in a
+    /// stable column order. A `None` value matches NULL.
+    pub fn push_where(
+        &mut self,
+        table_info: &TableInfo,
+        conditions: &HashMap<String, Option<String>>,
+    ) -> &mut Self {
+        let mut columns: Vec<&String> = conditions.keys().collect();
+        columns.sort();
+
+        for (i, column) in columns.into_iter().enumerate() {
+            self.push(if i == 0 { " WHERE " } else { " AND " });
+            match &conditions[column] {
+                Some(value) => {
+                    let data_type = column_type(&self.database_type, table_info, column);
+                    self.push_equals(column, value.clone(), &data_type);
+                }
+                None => {
+                    self.push_identifier(column).push(" IS NULL");
+                }
+            }
+        }
+        self
+    }
+
+    /// Append a WHERE clause applying the content grid filters. Filters on
+    /// unknown columns or without the values they need are skipped.
+    pub fn push_filters(&mut self, table_info: &TableInfo, filters: &[ColumnFilter]) -> &mut Self {
+        let mut first = true;
+        for filter in filters {
+            let Some(column) = table_info.columns.iter().find(|c| c.name == filter.column) else {
+                continue;
+            };
+            let value = filter.value.clone().filter(|v| !v.is_empty());
+            let to = filter.to.clone().filter(|v| !v.is_empty());
+            let applies = match filter.operator {
+                FilterOperator::Equals | FilterOperator::Contains => value.is_some(),
+                FilterOperator::Range => value.is_some() || to.is_some(),
+                FilterOperator::IsNull | FilterOperator::NotNull => true,
+            };
+            if !applies {
+                continue;
+            }
+
+            self.push(if first { " WHERE " } else { " AND " });
+            first = false;
+            let data_type = &column.data_type;
+            match filter.operator {
+                FilterOperator::Equals => {
+                    self.push_equals(&column.name, value.unwrap_or_default(), data_type);
+                }
+                FilterOperator::Contains => {
+                    let pattern = format!("%{}%", escape_like(&value.unwrap_or_default()));
+                    let quoted = self.database_type.quote_identifier(&column.name);
+                    let expression = match self.database_type {
+                        DatabaseType::Sqlite => format!("{} LIKE ", quoted),
+                        DatabaseType::Mysql => format!("CAST({} AS CHAR) LIKE ", quoted),
+                        DatabaseType::Postgres => format!("CAST({} AS text) ILIKE ", quoted),
+                    };
+                    self.push(&expression)
+                        .push_bind(Some(pattern), "text")
+                        .push(" ESCAPE '!'");
+                }
+                FilterOperator::Range => {
+                    if let Some(value) = value {
+                        self.push_identifier(&column.name).push(" >= ");
+                        self.push_bind(Some(value), data_type);
+                        if to.is_some() {
+                            self.push(" AND ");
+                        }
+                    }
+                    if let Some(to) = to {
+                        self.push_identifier(&column.name).push(" <= ");
+                        self.push_bind(Some(to), data_type);
+                    }
+                }
+                FilterOperator::IsNull => {
+                    self.push_identifier(&column.name).push(" IS NULL");
+                }
+                FilterOperator::NotNull => {
+                    self.push_identifier(&column.name).push(" IS NOT NULL");
+                }
+            }
+        }
+        self
+    }
+
+    /// Append an ORDER BY clause over `(column, descending)` pairs.
+    pub fn push_order_by(&mut self, order: &[(String, bool)]) -> &mut Self {
+        for (i, (column, descending)) in order.iter().enumerate() {
+            self.push(if i == 0 { " ORDER BY " } else { ", " });
+            self.push_identifier(column)
+                .push(if *descending { " DESC" } else { " ASC" });
+        }
+        self
+    }
+
+    #[cfg(test)]
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    #[cfg(test)]
+    pub fn params(&self) -> &[CellValue] {
+        &self.params
+    }
+
+    pub fn build(&self) -> Query<'_, Any, AnyArguments<'_>> {
+        let mut query = sqlx::query(&self.sql);
+        for param in &self.params {
+            query = match param {
+                CellValue::Null => query.bind(Option::<String>::None),
+                CellValue::Bool(b) => query.bind(*b),
+                CellValue::Int(i) => query.bind(*i),
+                CellValue::Float(f) => query.bind(*f),
+                CellValue::Bytes(b) => query.bind(b.clone()),
+                CellValue::Text(s) | CellValue::DateTime(s) => query.bind(s.clone()),
+                CellValue::Json(j) => query.bind(j.to_string()),
+            };
+        }
+        query
+    }
+}
+
+/// Type of `column` as reported by the table definition, including the row
+/// id pseudo-column used for tables without a primary key.
+pub fn column_type(database_type: &DatabaseType, table_info: &TableInfo, column: &str) -> String {
+    if let Some((row_id, data_type, _)) = database_type.row_id_column()
+        && row_id == column
+    {
+        return data_type.to_string();
+    }
+    table_info
+        .columns
+        .iter()
+        .find(|c| c.name == column)
+        .map(|c| c.data_type.clone())
+        .unwrap_or_default()
+}
+
+fn sorted_values<V>(data: &HashMap<String, V>) -> Vec<(&String, &V)> {
+    let mut values: Vec<_> = data.iter().collect();
+    values.sort_by(|a, b| a.0.cmp(b.0));
+    values
+}
+
+fn postgres_cast_type(database_type: &DatabaseType, data_type: &str) -> Option<String> {
+    if !matches!(database_type, DatabaseType::Postgres) {
+        return None;
+    }
+    // An explicit cast to varchar(5) or bit(3) silently truncates, so cast
+    // to the unmodified type and let assignment apply the column's length.
+    match without_type_modifier(data_type).as_str() {
+        "" | "text" => None,
+        // Bare `character` and `bit` mean length 1; cast to the unbounded forms.
+        "character" => Some("bpchar".to_string()),
+        "bit" => Some("varbit".to_string()),
+        other => Some(other.to_string()),
+    }
+}
+
+/// A Postgres type name without its modifier, e.g. `numeric` for
+/// `numeric(10,2)` or `timestamp without time zone` for
+/// `timestamp(3) without time zone`.
+fn without_type_modifier(data_type: &str) -> String {
+    match (data_type.find('('), data_type.find(')')) {
+        (Some(open), Some(close)) if open < close => {
+            format!("{}{}", &data_type[..open], &data_type[close + 1..])
+        }
+        _ => data_type.to_string(),
+    }
+}
+
+/// Escape LIKE wildcards in `value`, using `!` as the escape character.
+fn escape_like(value: &str) -> String {
+    value
+        .replace('!', "!!")
+        .replace('%', "!%")
+        .replace('_', "!_")
+}
+
+/// `INSERT INTO table (columns...) VALUES (params...)`, or `DEFAULT VALUES`
+/// when no column is given. Columns set to their default are left out.
+pub fn insert(
+    database_type: &DatabaseType,
+    table_info: &TableInfo,
+    request: &InsertRequest,
+) -> QueryBuilder {
+    let values: Vec<(&String, &CellValue)> = sorted_values(&request.data)
+        .into_iter()
+        .filter_map(|(column, value)| match value {
+            ColumnValue::Value(value) => Some((column, value)),
+            ColumnValue::Default => None,
+        })
+        .collect();
+    let mut builder = QueryBuilder::new(database_type);
+    builder.push("INSERT INTO ").push_table(&request.table);
+
+    if values.is_empty() {
+        builder.push(" DEFAULT VALUES");
+        return builder;
+    }
+
+    builder.push(" (");
+    for (i, (column, _)) in values.iter().enumerate() {
+        if i > 0 {
+            builder.push(", ");
+        }
+        builder.push_identifier(column);
+    }
+    builder.push(") VALUES (");
+    for (i, (column, value)) in values.iter().enumerate() {
+        if i > 0 {
+            builder.push(", ");
+        }
+        let data_type = column_type(database_type, table_info, column);
+        builder.push_value((*value).clone(), &data_type);
+    }
+    builder.push(")");
+    builder
+}
+
+/// `UPDATE table SET column = param, ... WHERE key = param AND ...`. SQLite
+/// has no `DEFAULT` in UPDATE, so there the default expression is repeated.
+pub fn update(
+    database_type: &DatabaseType,
+    table_info: &TableInfo,
+    request: &UpdateRequest,
+) -> QueryBuilder {
+    let values = sorted_values(&request.data);
+    let mut builder = QueryBuilder::new(database_type);
+    builder
+        .push("UPDATE ")
+        .push_table(&request.table)
+        .push(" SET ");
+    for (i, (column, value)) in values.iter().enumerate() {
+        if i > 0 {
+            builder.push(", ");
+        }
+        builder.push_identifier(column).push(" = ");
+        match value {
+            ColumnValue::Value(value) => {
+                let data_type = column_type(database_type, table_info, column);
+                builder.push_value(value.clone(), &data_type);
+            }
+            ColumnValue::Default if matches!(database_type, DatabaseType::Sqlite) => {
+                let default = table_info
+                    .columns
+                    .iter()
+                    .find(|c| &c.name == *column)
+                    .and_then(|c| c.default_value.clone());
+                builder.push(&format!("({})", default.as_deref().unwrap_or("NULL")));
+            }
+            ColumnValue::Default => {
+                builder.push("DEFAULT");
+            }
+        }
+    }
+    builder.push_where(table_info, &request.where_clause);
+    builder
+}
+
+/// Expression selecting `column` in a form the Any driver can decode.
+/// Booleans, dates, decimals, JSON and the like are converted to a supported
+/// representation and recovered afterwards with `CellValue::refine`.
+pub fn select_expression(database_type: &DatabaseType, column: &ColumnInfo) -> String {
+    let quoted = database_type.quote_identifier(&column.name);
+    let data_type = column.data_type.to_lowercase();
+    let base_type = data_type
+        .split(['(', ' '])
+        .next()
+        .unwrap_or_default()
+        .to_string();
+
+    let expression = match database_type {
+        // Unary plus drops the declared type, so the value's storage class is used.
+        DatabaseType::Sqlite => match base_type.as_str() {
+            "bool" | "boolean" | "date" | "time" | "datetime" | "timestamp" => {
+                format!("+{}", quoted)
+            }
+            _ => return quoted,
+        },
+        DatabaseType::Postgres => match without_type_modifier(&data_type).as_str() {
+            "smallint" | "integer" | "bigint" | "real" | "double precision" | "bytea" | "text"
+            | "character varying" => return quoted,
+            _ => format!("{}::text", quoted),
+        },
+        DatabaseType::Mysql => match base_type.as_str() {
+            "smallint" | "int" | "integer" | "bigint" | "float" | "double" | "char" | "varchar"
+            | "tinytext" | "text" | "mediumtext" | "longtext" | "binary" | "varbinary"
+            | "tinyblob" | "blob" | "mediumblob" | "longblob" => return quoted,
+            "tinyint" | "mediumint" | "year" | "bit" => format!("CAST({} AS SIGNED)", quoted),
+            _ => format!("CAST({} AS CHAR)", quoted),
+        },
+    };
+
+    format!("{} AS {}", expression, quoted)
+}
+
+/// Select list covering `columns`, see `select_expression`.
+pub fn select_list(database_type: &DatabaseType, columns: &[ColumnInfo]) -> String {
+    columns
+        .iter()
+        .map(|c| select_expression(database_type, c))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// `SELECT columns FROM table WHERE key = param AND ...`.
+pub fn select_where(
+    database_type: &DatabaseType,
+    table_info: &TableInfo,
+    conditions: &HashMap<String, Option<String>>,
+) -> QueryBuilder {
+    let mut builder = QueryBuilder::new(database_type);
+    builder
+        .push("SELECT ")
+        .push(&select_list(database_type, &table_info.columns))
+        .push(" FROM ")
+        .push_table(&table_info.qualified_name())
+        .push_where(table_info, conditions);
+    builder
+}
+
+/// `DELETE FROM table WHERE key = param AND ...`.
+pub fn delete(
+    database_type: &DatabaseType,
+    table_info: &TableInfo,
+    conditions: &HashMap<String, Option<String>>,
+) -> QueryBuilder {
+    let mut builder = QueryBuilder::new(database_type);
+    builder
+        .push("DELETE FROM ")
+        .push_table(&table_info.qualified_name())
+        .push_where(table_info, conditions);
+    builder
+}
+
+/// One page of the table content grid: `SELECT columns [, extra] FROM table
+/// WHERE filters ORDER BY ... LIMIT n OFFSET m`. `extra` is raw SQL appended
+/// to the select list, such as the row id expression.
+pub fn select_page(
+    database_type: &DatabaseType,
+    table_info: &TableInfo,
+    extra: &str,
+    filters: &[ColumnFilter],
+    order: &[(String, bool)],
+    limit: usize,
+    offset: usize,
+) -> QueryBuilder {
+    let mut builder = QueryBuilder::new(database_type);
+    builder
+        .push("SELECT ")
+        .push(&select_list(database_type, &table_info.columns))
+        .push(extra)
+        .push(" FROM ")
+        .push_table(&table_info.qualified_name())
+        .push_filters(table_info, filters)
+        .push_order_by(order)
+        .push(&format!(" LIMIT {} OFFSET {}", limit, offset));
+    builder
+}
+
+/// `SELECT COUNT(*) FROM table WHERE filters`.
+pub fn count_filtered(
+    database_type: &DatabaseType,
+    table_info: &TableInfo,
+    filters: &[ColumnFilter],
+) -> QueryBuilder {
+    let mut builder = QueryBuilder::new(database_type);
+    builder
+        .push("SELECT COUNT(*) AS count FROM ")
+        .push_table(&table_info.qualified_name())
+        .push_filters(table_info, filters);
+    builder
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(columns: &[(&str, &str)]) -> TableInfo {
+        TableInfo {
+            name: "items".to_string(),
+            schema: None,
+            columns: columns
+                .iter()
+                .map(|(name, data_type)| ColumnInfo::new(name, data_type))
+                .collect(),
+            is_view: false,
+        }
+    }
+
+    #[test]
+    fn insert_binds_values_in_column_order() {
+        let table = table(&[("id", "integer"), ("name", "character varying(20)")]);
+        let request = InsertRequest {
+            table: QualifiedName::new(Some("s2".to_string()), "items"),
+            data: HashMap::from([
+                (
+                    "name".to_string(),
+                    ColumnValue::Value(CellValue::Text("a'b".to_string())),
+                ),
+                ("id".to_string(), ColumnValue::Value(CellValue::Int(1))),
+                ("created".to_string(), ColumnValue::Default),
+            ]),
+        };
+
+        let builder = insert(&DatabaseType::Postgres, &table, &request);
+        assert_eq!(
+            builder.sql,
+            "INSERT INTO \"s2\".\"items\" (\"id\", \"name\") \
+             VALUES (CAST($1 AS integer), CAST($2 AS character varying))"
+        );
+        assert_eq!(
+            builder.params,
+            [CellValue::Int(1), CellValue::Text("a'b".to_string())]
+        );
+
+        let request = InsertRequest {
+            data: HashMap::from([("id".to_string(), ColumnValue::Default)]),
+            ..request
+        };
+        let builder = insert(&DatabaseType::Mysql, &table, &request);
+        assert_eq!(builder.sql, "INSERT INTO `s2`.`items` DEFAULT VALUES");
+    }
+
+    #[test]
+    fn update_repeats_sqlite_defaults_and_writes_null_literally() {
+        let mut table = table(&[("id", "INTEGER"), ("n", "INTEGER"), ("note", "TEXT")]);
+        table.columns[1].default_value = Some("0".to_string());
+        let request = UpdateRequest {
+            table: table.qualified_name(),
+            data: HashMap::from([
+                ("n".to_string(), ColumnValue::Default),
+                ("note".to_string(), ColumnValue::Value(CellValue::Null)),
+            ]),
+            where_clause: HashMap::from([("id".to_string(), Some("7".to_string()))]),
+        };
+
+        let builder = update(&DatabaseType::Sqlite, &table, &request);
+        assert_eq!(
+            builder.sql,
+            "UPDATE \"items\" SET \"n\" = (0), \"note\" = NULL WHERE \"id\" = ?"
+        );
+        assert_eq!(builder.params, [CellValue::Text("7".to_string())]);
+
+        let builder = update(&DatabaseType::Postgres, &table, &request);
+        assert_eq!(
+            builder.sql,
+            "UPDATE \"items\" SET \"n\" = DEFAULT, \"note\" = NULL \
+             WHERE \"id\" = CAST($1 AS INTEGER)"
+        );
+    }
+
+    #[test]
+    fn delete_matches_every_key_and_nulls() {
+        let table = table(&[("a", "text"), ("b", "uuid")]);
+        let conditions = HashMap::from([
+            (
+                "b".to_string(),
+                Some("00000000-0000-0000-0000-000000000000".to_string()),
+            ),
+            ("a".to_string(), None),
+        ]);
+
+        let builder = delete(&DatabaseType::Postgres, &table, &conditions);
+        assert_eq!(
+            builder.sql,
+            "DELETE FROM \"items\" WHERE \"a\" IS NULL AND \"b\" = CAST($1 AS uuid)"
+        );
+
+        // The row id pseudo-column takes its type from the backend.
+        let conditions = HashMap::from([("ctid".to_string(), Some("(0,1)".to_string()))]);
+        let builder = delete(&DatabaseType::Postgres, &table, &conditions);
+        assert_eq!(
+            builder.sql,
+            "DELETE FROM \"items\" WHERE \"ctid\" = CAST($1 AS tid)"
+        );
+    }
+
+    #[test]
+    fn postgres_casts_drop_type_modifiers() {
+        let cast = |data_type| postgres_cast_type(&DatabaseType::Postgres, data_type);
+        assert_eq!(cast("text"), None);
+        assert_eq!(cast("numeric(10,2)").as_deref(), Some("numeric"));
+        assert_eq!(
+            cast("timestamp(3) without time zone").as_deref(),
+            Some("timestamp without time zone")
+        );
+        assert_eq!(cast("character(4)").as_deref(), Some("bpchar"));
+        assert_eq!(cast("bit(3)").as_deref(), Some("varbit"));
+        assert_eq!(postgres_cast_type(&DatabaseType::Sqlite, "INTEGER"), None);
+    }
+}
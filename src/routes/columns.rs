@@ -1,5 +1,10 @@
-use crate::config::{DatabaseConfig, DatabaseManager, DatabasePool};
+use crate::config::{DatabaseConfig, DatabaseManager, DatabasePool, DatabaseType, TableInfo};
+use crate::models::{
+    AddColumnRequest, CreateColumnRequest, DropColumnRequest, ModifyColumnRequest,
+    RenameColumnRequest,
+};
 use crate::routes::index::AuthGuard;
+use crate::routes::schema::Schema;
 use crate::template::{IntoTemplateResponse, TemplateResponse};
 use askama::Template;
 use rocket::form::Form;
@@ -12,6 +17,7 @@ use rocket::{get, post, State};
 pub struct AddColumnTemplate {
     pub table_name: String,
     pub database_name: String,
+    pub schema_prefix: String,
     pub readonly: bool,
     pub flash_messages: Vec<crate::models::FlashMessage>,
     pub version: String,
@@ -23,7 +29,10 @@ pub struct AddColumnTemplate {
 pub struct DropColumnTemplate {
     pub table_name: String,
     pub columns: Vec<String>,
+    // Indexes removed along with a column, as (column, index) pairs.
+    pub dropped_indexes: Vec<(String, String)>,
     pub database_name: String,
+    pub schema_prefix: String,
     pub readonly: bool,
     pub flash_messages: Vec<crate::models::FlashMessage>,
     pub version: String,
@@ -36,6 +45,27 @@ pub struct RenameColumnTemplate {
     pub table_name: String,
     pub columns: Vec<String>,
     pub database_name: String,
+    pub schema_prefix: String,
+    pub readonly: bool,
+    pub flash_messages: Vec<crate::models::FlashMessage>,
+    pub version: String,
+    pub error: String,
+}
+
+#[derive(Template)]
+#[template(path = "modify_column.html")]
+pub struct ModifyColumnTemplate {
+    pub table_name: String,
+    pub columns: Vec<String>,
+    pub column: String,
+    pub data_type: String,
+    pub nullable: bool,
+    pub default_value: String,
+    pub column_types: Vec<String>,
+    // Statements the form generates, shown before they are applied.
+    pub statements: Vec<String>,
+    pub database_name: String,
+    pub schema_prefix: String,
     pub readonly: bool,
     pub flash_messages: Vec<crate::models::FlashMessage>,
     pub version: String,
@@ -62,19 +92,30 @@ pub struct RenameColumnForm {
     pub new_name: String,
 }
 
+#[derive(rocket::FromForm)]
+pub struct ModifyColumnForm {
+    pub column: String,
+    pub data_type: String,
+    pub nullable: bool,
+    pub default_value: Option<String>,
+    // Set by the Apply button once the statements have been previewed.
+    pub confirm: bool,
+}
+
 #[get("/table/<table_name>/add-column")]
 pub async fn add_column(
     table_name: String,
     db: &State<DatabasePool>,
     config: &State<DatabaseConfig>,
     _auth: AuthGuard,
+    schema: Schema,
 ) -> Result<TemplateResponse<AddColumnTemplate>, Status> {
     if config.readonly {
         return Err(Status::Forbidden);
     }
 
     let pool = &db.0;
-    let manager = DatabaseManager::new(pool, config.inner().clone());
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
 
     let database_info = manager
         .get_database_info()
@@ -84,6 +125,7 @@ pub async fn add_column(
     Ok(AddColumnTemplate {
         table_name: table_name.clone(),
         database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
         readonly: database_info.readonly,
         flash_messages: vec![],
         version: "0.1.0".to_string(),
@@ -99,37 +141,32 @@ pub async fn add_column_execute(
     db: &State<DatabasePool>,
     config: &State<DatabaseConfig>,
     _auth: AuthGuard,
+    schema: Schema,
 ) -> Result<Redirect, Status> {
     if config.readonly {
         return Err(Status::Forbidden);
     }
 
     let pool = &db.0;
-    let manager = DatabaseManager::new(pool, config.inner().clone());
-
-    let mut sql = format!(
-        "ALTER TABLE \"{}\" ADD COLUMN \"{}\" {}",
-        table_name, form.name, form.data_type
-    );
-
-    if form.nullable != Some(true) {
-        sql += " NOT NULL";
-    }
-
-    if let Some(ref default_val) = form.default_value {
-        if !default_val.is_empty() {
-            sql += &format!(" DEFAULT '{}'", default_val.replace("'", "''"));
-        }
-    }
-
-    if form.primary_key == Some(true) {
-        // Note: Adding primary key constraint to existing table is complex
-        // This is a simplified version that may not work for all databases
-        sql += " PRIMARY KEY";
-    }
-
-    match manager.execute_query(&sql).await {
-        Ok(_) => Ok(Redirect::to(format!("/table/{}/structure", table_name))),
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
+
+    let request = AddColumnRequest {
+        table: manager.qualify(&table_name),
+        column: CreateColumnRequest {
+            name: form.name.clone(),
+            data_type: form.data_type.clone(),
+            nullable: form.nullable == Some(true),
+            default_value: form.default_value.clone(),
+            primary_key: form.primary_key == Some(true),
+            auto_increment: false,
+            unique: false,
+        },
+    };
+
+    match manager.add_column(&request).await {
+        Ok(_) => Ok(Redirect::to(
+            schema.path(&format!("/table/{}/structure", table_name)),
+        )),
         Err(_) => Err(Status::InternalServerError),
     }
 }
@@ -140,13 +177,14 @@ pub async fn drop_column(
     db: &State<DatabasePool>,
     config: &State<DatabaseConfig>,
     _auth: AuthGuard,
+    schema: Schema,
 ) -> Result<TemplateResponse<DropColumnTemplate>, Status> {
     if config.readonly {
         return Err(Status::Forbidden);
     }
 
     let pool = &db.0;
-    let manager = DatabaseManager::new(pool, config.inner().clone());
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
 
     let database_info = manager
         .get_database_info()
@@ -154,16 +192,19 @@ pub async fn drop_column(
         .map_err(|_| Status::InternalServerError)?;
 
     let table_info = manager
-        .get_table_info(&table_name)
+        .get_table_info(&manager.qualify(&table_name))
         .await
         .map_err(|_| Status::InternalServerError)?;
 
     let columns: Vec<String> = table_info.columns.iter().map(|c| c.name.clone()).collect();
+    let dropped_indexes = dropped_indexes(&manager, &table_info, &config.database_type).await?;
 
     Ok(DropColumnTemplate {
         table_name: table_name.clone(),
         columns,
+        dropped_indexes,
         database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
         readonly: database_info.readonly,
         flash_messages: vec![],
         version: "0.1.0".to_string(),
@@ -179,23 +220,73 @@ pub async fn drop_column_execute(
     db: &State<DatabasePool>,
     config: &State<DatabaseConfig>,
     _auth: AuthGuard,
-) -> Result<Redirect, Status> {
+    schema: Schema,
+) -> Result<Result<Redirect, TemplateResponse<DropColumnTemplate>>, Status> {
     if config.readonly {
         return Err(Status::Forbidden);
     }
 
     let pool = &db.0;
-    let manager = DatabaseManager::new(pool, config.inner().clone());
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
+
+    let request = DropColumnRequest {
+        table: manager.qualify(&table_name),
+        column: form.column_name.clone(),
+    };
+
+    let error = match manager.drop_column(&request).await {
+        Ok(_) => {
+            return Ok(Ok(Redirect::to(
+                schema.path(&format!("/table/{}/structure", table_name)),
+            )));
+        }
+        Err(e) => format!("SQL Error: {}", e),
+    };
 
-    let sql = format!(
-        "ALTER TABLE \"{}\" DROP COLUMN \"{}\"",
-        table_name, form.column_name
-    );
+    let database_info = manager
+        .get_database_info()
+        .await
+        .map_err(|_| Status::InternalServerError)?;
 
-    match manager.execute_query(&sql).await {
-        Ok(_) => Ok(Redirect::to(format!("/table/{}/structure", table_name))),
-        Err(_) => Err(Status::InternalServerError),
+    let table_info = manager
+        .get_table_info(&request.table)
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    Ok(Err(DropColumnTemplate {
+        table_name: table_name.clone(),
+        columns: table_info.columns.iter().map(|c| c.name.clone()).collect(),
+        dropped_indexes: dropped_indexes(&manager, &table_info, &config.database_type).await?,
+        database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
+        readonly: database_info.readonly,
+        flash_messages: vec![],
+        version: "0.1.0".to_string(),
+        error,
     }
+    .into_template_response()))
+}
+
+/// Indexes of the table that dropping each of its columns would remove.
+async fn dropped_indexes(
+    manager: &DatabaseManager<'_>,
+    table_info: &TableInfo,
+    database_type: &DatabaseType,
+) -> Result<Vec<(String, String)>, Status> {
+    let indexes = manager
+        .get_indexes(&manager.qualify(&table_info.name))
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    Ok(table_info
+        .columns
+        .iter()
+        .flat_map(|column| {
+            indexes
+                .iter()
+                .filter(|index| index.dropped_with(&column.name, database_type))
+                .map(|index| (column.name.clone(), index.name.clone()))
+        })
+        .collect())
 }
 
 #[get("/table/<table_name>/rename-column")]
@@ -204,13 +295,14 @@ pub async fn rename_column(
     db: &State<DatabasePool>,
     config: &State<DatabaseConfig>,
     _auth: AuthGuard,
+    schema: Schema,
 ) -> Result<TemplateResponse<RenameColumnTemplate>, Status> {
     if config.readonly {
         return Err(Status::Forbidden);
     }
 
     let pool = &db.0;
-    let manager = DatabaseManager::new(pool, config.inner().clone());
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
 
     let database_info = manager
         .get_database_info()
@@ -218,7 +310,7 @@ pub async fn rename_column(
         .map_err(|_| Status::InternalServerError)?;
 
     let table_info = manager
-        .get_table_info(&table_name)
+        .get_table_info(&manager.qualify(&table_name))
         .await
         .map_err(|_| Status::InternalServerError)?;
 
@@ -228,6 +320,7 @@ pub async fn rename_column(
         table_name: table_name.clone(),
         columns,
         database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
         readonly: database_info.readonly,
         flash_messages: vec![],
         version: "0.1.0".to_string(),
@@ -243,21 +336,189 @@ pub async fn rename_column_execute(
     db: &State<DatabasePool>,
     config: &State<DatabaseConfig>,
     _auth: AuthGuard,
-) -> Result<Redirect, Status> {
+    schema: Schema,
+) -> Result<Result<Redirect, TemplateResponse<RenameColumnTemplate>>, Status> {
     if config.readonly {
         return Err(Status::Forbidden);
     }
 
     let pool = &db.0;
-    let manager = DatabaseManager::new(pool, config.inner().clone());
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
+
+    let request = RenameColumnRequest {
+        table: manager.qualify(&table_name),
+        old_name: form.old_name.clone(),
+        new_name: form.new_name.clone(),
+    };
+
+    let error = match manager.rename_column(&request).await {
+        Ok(_) => {
+            return Ok(Ok(Redirect::to(
+                schema.path(&format!("/table/{}/structure", table_name)),
+            )));
+        }
+        Err(e) => format!("SQL Error: {}", e),
+    };
 
-    let sql = format!(
-        "ALTER TABLE \"{}\" RENAME COLUMN \"{}\" TO \"{}\"",
-        table_name, form.old_name, form.new_name
-    );
+    let database_info = manager
+        .get_database_info()
+        .await
+        .map_err(|_| Status::InternalServerError)?;
 
-    match manager.execute_query(&sql).await {
-        Ok(_) => Ok(Redirect::to(format!("/table/{}/structure", table_name))),
-        Err(_) => Err(Status::InternalServerError),
+    let table_info = manager
+        .get_table_info(&request.table)
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    Ok(Err(RenameColumnTemplate {
+        table_name: table_name.clone(),
+        columns: table_info.columns.iter().map(|c| c.name.clone()).collect(),
+        database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
+        readonly: database_info.readonly,
+        flash_messages: vec![],
+        version: "0.1.0".to_string(),
+        error,
+    }
+    .into_template_response()))
+}
+
+#[get("/table/<table_name>/modify-column?<column>")]
+pub async fn modify_column(
+    table_name: String,
+    column: Option<String>,
+    db: &State<DatabasePool>,
+    config: &State<DatabaseConfig>,
+    _auth: AuthGuard,
+    schema: Schema,
+) -> Result<TemplateResponse<ModifyColumnTemplate>, Status> {
+    if config.readonly {
+        return Err(Status::Forbidden);
+    }
+
+    let pool = &db.0;
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
+
+    let database_info = manager
+        .get_database_info()
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    let table_info = manager
+        .get_table_info(&manager.qualify(&table_name))
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    // Pre-fill the form from the column as it is now.
+    let current = match &column {
+        Some(column) => table_info.columns.iter().find(|c| &c.name == column),
+        None => table_info.columns.first(),
+    }
+    .ok_or(Status::NotFound)?;
+
+    Ok(ModifyColumnTemplate {
+        table_name: table_name.clone(),
+        columns: table_info.columns.iter().map(|c| c.name.clone()).collect(),
+        column: current.name.clone(),
+        data_type: current.data_type.clone(),
+        nullable: current.nullable,
+        default_value: current.default_value.clone().unwrap_or_default(),
+        column_types: config
+            .database_type
+            .column_types()
+            .iter()
+            .map(|t| t.to_string())
+            .collect(),
+        statements: vec![],
+        database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
+        readonly: database_info.readonly,
+        flash_messages: vec![],
+        version: "0.1.0".to_string(),
+        error: "".to_string(),
+    }
+    .into_template_response())
+}
+
+#[post("/table/<table_name>/modify-column", data = "<form>")]
+pub async fn modify_column_execute(
+    table_name: String,
+    form: Form<ModifyColumnForm>,
+    db: &State<DatabasePool>,
+    config: &State<DatabaseConfig>,
+    _auth: AuthGuard,
+    schema: Schema,
+) -> Result<Result<Redirect, TemplateResponse<ModifyColumnTemplate>>, Status> {
+    if config.readonly {
+        return Err(Status::Forbidden);
+    }
+
+    let pool = &db.0;
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
+
+    let database_info = manager
+        .get_database_info()
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    let table_info = manager
+        .get_table_info(&manager.qualify(&table_name))
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    let request = ModifyColumnRequest {
+        table: manager.qualify(&table_name),
+        column: form.column.clone(),
+        data_type: form.data_type.trim().to_string(),
+        nullable: form.nullable,
+        default_value: form.default_value.clone().filter(|v| !v.is_empty()),
+    };
+
+    let mut statements = vec![];
+    let error = if request.data_type.is_empty() {
+        "The column needs a type".to_string()
+    } else {
+        match manager.modify_column_statements(&request).await {
+            Ok(generated) if generated.is_empty() => "Nothing to change".to_string(),
+            Ok(generated) => {
+                statements = generated;
+                if form.confirm {
+                    match manager.modify_column(&request).await {
+                        Ok(()) => {
+                            return Ok(Ok(Redirect::to(
+                                schema.path(&format!("/table/{}/structure", table_name)),
+                            )));
+                        }
+                        Err(e) => format!("SQL Error: {}", e),
+                    }
+                } else {
+                    "".to_string()
+                }
+            }
+            Err(e) => format!("SQL Error: {}", e),
+        }
+    };
+
+    Ok(Err(ModifyColumnTemplate {
+        table_name: table_name.clone(),
+        columns: table_info.columns.iter().map(|c| c.name.clone()).collect(),
+        column: request.column,
+        data_type: request.data_type,
+        nullable: request.nullable,
+        default_value: request.default_value.unwrap_or_default(),
+        column_types: config
+            .database_type
+            .column_types()
+            .iter()
+            .map(|t| t.to_string())
+            .collect(),
+        statements,
+        database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
+        readonly: database_info.readonly,
+        flash_messages: vec![],
+        version: "0.1.0".to_string(),
+        error,
     }
+    .into_template_response()))
 }
@@ -1,5 +1,8 @@
 use crate::config::{DatabaseConfig, DatabaseManager, DatabasePool};
-use crate::models::{DatabaseStats, FlashMessage as Flash};
+use crate::models::{
+    format_file_size, ConnectionInfo, DatabaseStats, FlashMessage as Flash, ViewInfo,
+};
+use crate::routes::schema::Schema;
 use crate::Args;
 
 use crate::template::{IntoTemplateResponse, TemplateResponse};
@@ -15,27 +18,43 @@ use rocket::{get, post, State};
 #[template(path = "index.html")]
 pub struct IndexTemplate {
     pub database_stats: DatabaseStats,
+    pub connection_info: ConnectionInfo,
     pub tables: Vec<String>,
+    pub views: Vec<ViewInfo>,
+    pub schemas: Vec<String>,
+    pub current_schema: String,
+    pub schema_prefix: String,
     pub version: String,
     pub flash_messages: Vec<Flash>,
     pub file_size_display: String,
 }
 
 impl IndexTemplate {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         database_stats: DatabaseStats,
+        connection_info: ConnectionInfo,
         tables: Vec<String>,
+        views: Vec<ViewInfo>,
+        schemas: Vec<String>,
+        current_schema: Option<String>,
+        schema: &Schema,
         version: String,
         flash_messages: Vec<Flash>,
     ) -> Self {
         let file_size_display = match database_stats.file_size {
-            Some(size) => format!("{} bytes", size),
+            Some(size) => format_file_size(size),
             None => "Unknown".to_string(),
         };
 
         Self {
             database_stats,
+            connection_info,
             tables,
+            views,
+            schemas,
+            current_schema: current_schema.unwrap_or_default(),
+            schema_prefix: schema.prefix(),
             version,
             flash_messages,
             file_size_display,
@@ -62,7 +81,7 @@ impl<'r> FromRequest<'r> for AuthGuard {
 
     async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
         let cookies = request.cookies();
-        if let Some(_) = cookies.get_private("authenticated") {
+        if cookies.get_private("authenticated").is_some() {
             Outcome::Success(AuthGuard)
         } else {
             Outcome::Error((Status::Unauthorized, ()))
@@ -76,9 +95,10 @@ pub async fn index(
     config: &State<DatabaseConfig>,
     _args: &State<Args>,
     _auth: AuthGuard,
+    schema: Schema,
 ) -> Result<TemplateResponse<IndexTemplate>, Status> {
     let pool = &db.0;
-    let manager = DatabaseManager::new(pool, config.inner().clone());
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
 
     let database_info = manager
         .get_database_info()
@@ -90,23 +110,50 @@ pub async fn index(
         .await
         .map_err(|_| Status::InternalServerError)?;
 
+    let triggers = manager.get_triggers(None).await.unwrap_or_default();
+    let views = manager.get_views().await.unwrap_or_default();
+    let schemas = manager.get_schemas().await.unwrap_or_default();
+    let current_schema = manager.current_schema().await.ok().flatten();
+    let index_count = manager.get_index_count().await.unwrap_or_default();
+    let table_stats = manager.get_table_stats().await.unwrap_or_default();
+    let pragmas = manager.get_pragmas().await.unwrap_or_default();
+    let server_version = manager.get_server_version().await.ok();
+
+    let connection_info = ConnectionInfo {
+        database_url: manager.display_url(),
+        database_type: format!("{:?}", database_info.database_type),
+        readonly: database_info.readonly,
+        connected: true,
+        version: server_version,
+    };
+
     let database_stats = DatabaseStats {
         database_name: database_info.base_name(),
         database_type: format!("{:?}", database_info.database_type),
         file_size: database_info.size,
         table_count: tables.len(),
-        index_count: 0,   // TODO: Implement index counting
-        trigger_count: 0, // TODO: Implement trigger counting
-        view_count: 0,    // TODO: Implement view counting
+        index_count: index_count as usize,
+        trigger_count: triggers.len(),
+        view_count: views.len(),
         created: database_info.created,
         modified: database_info.modified,
         readonly: database_info.readonly,
+        table_stats,
+        pragmas,
     };
 
-    Ok(
-        IndexTemplate::new(database_stats, tables, "0.1.0".to_string(), vec![])
-            .into_template_response(),
+    Ok(IndexTemplate::new(
+        database_stats,
+        connection_info,
+        tables,
+        views,
+        schemas,
+        current_schema,
+        &schema,
+        "0.1.0".to_string(),
+        vec![],
     )
+    .into_template_response())
 }
 
 #[get("/", rank = 2)]
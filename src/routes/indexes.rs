@@ -1,6 +1,7 @@
 use crate::config::{DatabaseConfig, DatabaseManager, DatabasePool};
-use crate::models::FlashMessage as Flash;
+use crate::models::{CreateIndexRequest, DropIndexRequest, FlashMessage as Flash};
 use crate::routes::index::AuthGuard;
+use crate::routes::schema::Schema;
 use crate::template::{IntoTemplateResponse, TemplateResponse};
 use askama::Template;
 use rocket::form::Form;
@@ -14,6 +15,7 @@ pub struct AddIndexTemplate {
     pub table_name: String,
     pub columns: Vec<String>,
     pub database_name: String,
+    pub schema_prefix: String,
     pub readonly: bool,
     pub flash_messages: Vec<Flash>,
     pub version: String,
@@ -26,6 +28,7 @@ pub struct DropIndexTemplate {
     pub table_name: String,
     pub indexes: Vec<String>,
     pub database_name: String,
+    pub schema_prefix: String,
     pub readonly: bool,
     pub flash_messages: Vec<Flash>,
     pub version: String,
@@ -50,13 +53,14 @@ pub async fn add_index(
     db: &State<DatabasePool>,
     config: &State<DatabaseConfig>,
     _auth: AuthGuard,
+    schema: Schema,
 ) -> Result<TemplateResponse<AddIndexTemplate>, Status> {
     if config.readonly {
         return Err(Status::Forbidden);
     }
 
     let pool = &db.0;
-    let manager = DatabaseManager::new(pool, config.inner().clone());
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
 
     let database_info = manager
         .get_database_info()
@@ -64,7 +68,7 @@ pub async fn add_index(
         .map_err(|_| Status::InternalServerError)?;
 
     let table_info = manager
-        .get_table_info(&table_name)
+        .get_table_info(&manager.qualify(&table_name))
         .await
         .map_err(|_| Status::InternalServerError)?;
 
@@ -74,6 +78,7 @@ pub async fn add_index(
         table_name: table_name.clone(),
         columns,
         database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
         readonly: database_info.readonly,
         flash_messages: vec![],
         version: "0.1.0".to_string(),
@@ -89,34 +94,31 @@ pub async fn add_index_execute(
     db: &State<DatabasePool>,
     config: &State<DatabaseConfig>,
     _auth: AuthGuard,
+    schema: Schema,
 ) -> Result<Redirect, Status> {
     if config.readonly {
         return Err(Status::Forbidden);
     }
 
     let pool = &db.0;
-    let manager = DatabaseManager::new(pool, config.inner().clone());
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
 
     let index_name = form.index_name.trim();
     if index_name.is_empty() || form.columns.is_empty() {
         return Err(Status::BadRequest);
     }
 
-    let unique_clause = if form.unique.unwrap_or(false) {
-        "UNIQUE "
-    } else {
-        ""
+    let request = CreateIndexRequest {
+        table: manager.qualify(&table_name),
+        name: index_name.to_string(),
+        columns: form.columns.clone(),
+        unique: form.unique.unwrap_or(false),
     };
 
-    let columns_str = form.columns.join(", ");
-
-    let sql = format!(
-        "CREATE {}INDEX \"{}\" ON \"{}\" ({})",
-        unique_clause, index_name, table_name, columns_str
-    );
-
-    match manager.execute_query(&sql).await {
-        Ok(_) => Ok(Redirect::to(format!("/table/{}/structure", table_name))),
+    match manager.create_index(&request).await {
+        Ok(_) => Ok(Redirect::to(
+            schema.path(&format!("/table/{}/structure", table_name)),
+        )),
         Err(_) => Err(Status::InternalServerError),
     }
 }
@@ -127,13 +129,14 @@ pub async fn drop_index(
     db: &State<DatabasePool>,
     config: &State<DatabaseConfig>,
     _auth: AuthGuard,
+    schema: Schema,
 ) -> Result<TemplateResponse<DropIndexTemplate>, Status> {
     if config.readonly {
         return Err(Status::Forbidden);
     }
 
     let pool = &db.0;
-    let manager = DatabaseManager::new(pool, config.inner().clone());
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
 
     let database_info = manager
         .get_database_info()
@@ -141,16 +144,22 @@ pub async fn drop_index(
         .map_err(|_| Status::InternalServerError)?;
 
     let indexes = manager
-        .get_indexes(&table_name)
+        .get_indexes(&manager.qualify(&table_name))
         .await
         .unwrap_or_else(|_| vec![]);
 
-    let index_names: Vec<String> = indexes.iter().map(|i| i.name.clone()).collect();
+    // Constraint indexes go away with their constraint, not DROP INDEX.
+    let index_names: Vec<String> = indexes
+        .iter()
+        .filter(|i| !i.from_constraint)
+        .map(|i| i.name.clone())
+        .collect();
 
     Ok(DropIndexTemplate {
         table_name: table_name.clone(),
         indexes: index_names,
         database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
         readonly: database_info.readonly,
         flash_messages: vec![],
         version: "0.1.0".to_string(),
@@ -166,18 +175,24 @@ pub async fn drop_index_execute(
     db: &State<DatabasePool>,
     config: &State<DatabaseConfig>,
     _auth: AuthGuard,
+    schema: Schema,
 ) -> Result<Redirect, Status> {
     if config.readonly {
         return Err(Status::Forbidden);
     }
 
     let pool = &db.0;
-    let manager = DatabaseManager::new(pool, config.inner().clone());
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
 
-    let sql = format!("DROP INDEX \"{}\"", form.index_name);
+    let request = DropIndexRequest {
+        table: manager.qualify(&table_name),
+        name: form.index_name.clone(),
+    };
 
-    match manager.execute_query(&sql).await {
-        Ok(_) => Ok(Redirect::to(format!("/table/{}/structure", table_name))),
+    match manager.drop_index(&request).await {
+        Ok(_) => Ok(Redirect::to(
+            schema.path(&format!("/table/{}/structure", table_name)),
+        )),
         Err(_) => Err(Status::InternalServerError),
     }
 }
@@ -2,6 +2,10 @@ pub mod columns;
 pub mod index;
 pub mod indexes;
 pub mod query;
+pub mod schema;
+pub mod table_admin;
 pub mod tables;
+pub mod triggers;
+pub mod views;
 
 // Re-exports are not needed since routes are imported directly in main.rs
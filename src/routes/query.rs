@@ -1,6 +1,9 @@
-use crate::config::{DatabaseConfig, DatabaseManager, DatabasePool};
-use crate::models::{QueryRequest, QueryResponse};
+use crate::config::{page_offset, DatabaseConfig, DatabaseManager, DatabasePool, StatementResult};
+use crate::models::{CellValue, QueryRequest, QueryResponse};
 use crate::routes::index::AuthGuard;
+use crate::routes::schema::Schema;
+use crate::statement;
+use crate::Args;
 
 use crate::template::{IntoTemplateResponse, TemplateResponse};
 use askama::Template;
@@ -16,44 +19,112 @@ pub struct QueryTemplate {
     pub sql: String,
     pub has_result: bool,
     pub columns: Vec<String>,
-    pub rows: Vec<Vec<String>>,
+    pub rows: Vec<Vec<CellValue>>,
     pub rows_affected_display: String,
+    pub last_insert_id_display: String,
     pub error: String,
     pub database_name: String,
+    pub schema_prefix: String,
+    pub statements: Vec<StatementOutput>,
+    pub use_transaction: bool,
+    pub rolled_back: bool,
+    // Page of a query's rows shown, and whether more rows follow it.
+    pub page: usize,
+    pub has_more: bool,
+}
+
+/// One statement of a multi-statement script, as shown in its result tab.
+pub struct StatementOutput {
+    pub sql: String,
+    pub executed: bool,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<CellValue>>,
+    pub rows_affected_display: String,
+    pub last_insert_id_display: String,
+    pub elapsed_display: String,
+    pub error: String,
+}
+
+impl StatementOutput {
+    fn from_result(result: StatementResult) -> Self {
+        let elapsed_display = format!("{:.1} ms", result.elapsed.as_secs_f64() * 1000.0);
+        match result.result {
+            Ok(query_result) => Self {
+                sql: result.sql,
+                executed: true,
+                columns: query_result.columns,
+                rows: query_result.rows,
+                rows_affected_display: match query_result.rows_affected {
+                    Some(count) => count.to_string(),
+                    None => String::new(),
+                },
+                last_insert_id_display: match query_result.last_insert_id {
+                    Some(id) => id.to_string(),
+                    None => String::new(),
+                },
+                elapsed_display,
+                error: String::new(),
+            },
+            Err(e) => Self {
+                error: format!("SQL Error: {}", e),
+                executed: true,
+                elapsed_display,
+                ..Self::not_executed(result.sql)
+            },
+        }
+    }
+
+    fn not_executed(sql: String) -> Self {
+        Self {
+            sql,
+            executed: false,
+            columns: vec![],
+            rows: vec![],
+            rows_affected_display: String::new(),
+            last_insert_id_display: String::new(),
+            elapsed_display: String::new(),
+            error: String::new(),
+        }
+    }
 }
 
 impl QueryTemplate {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         sql: String,
         has_result: bool,
         columns: Vec<String>,
-        rows: Vec<Vec<Option<String>>>,
+        rows: Vec<Vec<CellValue>>,
         rows_affected: Option<u64>,
+        last_insert_id: Option<i64>,
         error: String,
         database_name: String,
+        schema_prefix: String,
     ) -> Self {
         let rows_affected_display = match rows_affected {
             Some(count) => count.to_string(),
-            None => "0".to_string(),
+            None => String::new(),
+        };
+        let last_insert_id_display = match last_insert_id {
+            Some(id) => id.to_string(),
+            None => String::new(),
         };
-
-        let processed_rows = rows
-            .into_iter()
-            .map(|row| {
-                row.into_iter()
-                    .map(|cell| cell.unwrap_or_else(|| "NULL".to_string()))
-                    .collect()
-            })
-            .collect();
 
         Self {
             sql,
             has_result,
             columns,
-            rows: processed_rows,
+            rows,
             rows_affected_display,
+            last_insert_id_display,
             error,
             database_name,
+            schema_prefix,
+            statements: vec![],
+            use_transaction: false,
+            rolled_back: false,
+            page: 1,
+            has_more: false,
         }
     }
 }
@@ -63,8 +134,7 @@ pub struct QueryForm {
     sql: String,
     page: Option<usize>,
     ordering: Option<i32>,
-    export_json: Option<String>,
-    export_csv: Option<String>,
+    transaction: bool,
 }
 
 #[get("/query")]
@@ -72,9 +142,10 @@ pub async fn query_page(
     db: &State<DatabasePool>,
     config: &State<DatabaseConfig>,
     _auth: AuthGuard,
+    schema: Schema,
 ) -> Result<TemplateResponse<QueryTemplate>, Status> {
     let pool = &db.0;
-    let manager = DatabaseManager::new(pool, config.inner().clone());
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
 
     let database_info = manager
         .get_database_info()
@@ -87,8 +158,10 @@ pub async fn query_page(
         vec![],
         vec![],
         None,
+        None,
         String::new(),
         database_info.base_name(),
+        schema.prefix(),
     )
     .into_template_response())
 }
@@ -98,10 +171,12 @@ pub async fn execute_query(
     form: Form<QueryForm>,
     db: &State<DatabasePool>,
     config: &State<DatabaseConfig>,
+    args: &State<Args>,
     _auth: AuthGuard,
+    schema: Schema,
 ) -> Result<TemplateResponse<QueryTemplate>, Status> {
     let pool = &db.0;
-    let manager = DatabaseManager::new(pool, config.inner().clone());
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
 
     let database_info = manager
         .get_database_info()
@@ -116,98 +191,90 @@ pub async fn execute_query(
             vec![],
             vec![],
             None,
+            None,
             "SQL query cannot be empty".to_string(),
             database_info.base_name(),
+            schema.prefix(),
         )
         .into_template_response());
     }
 
-    // Check if this is a read-only connection and the query is a write operation
-    if config.readonly && is_write_operation(sql) {
+    let statements = statement::split(sql, &config.database_type);
+    if statements.is_empty() {
         return Ok(QueryTemplate::new(
             sql.to_string(),
             false,
             vec![],
             vec![],
             None,
-            "Write operations are not allowed in read-only mode".to_string(),
+            None,
+            "SQL query cannot be empty".to_string(),
             database_info.base_name(),
+            schema.prefix(),
         )
         .into_template_response());
     }
 
-    let page = form.page.unwrap_or(1);
-    let per_page = 1000; // Default query rows per page
-
-    // Handle export requests
-    if form.export_json.is_some() || form.export_csv.is_some() {
-        // TODO: Implement export functionality
-        // For now, fall through to regular query execution
+    // Check if this is a read-only connection and the query is a write operation
+    if config.readonly && !statements.iter().all(|s| statement::is_read_only(s)) {
+        return Ok(QueryTemplate::new(
+            sql.to_string(),
+            false,
+            vec![],
+            vec![],
+            None,
+            None,
+            "Write operations are not allowed in read-only mode".to_string(),
+            database_info.base_name(),
+            schema.prefix(),
+        )
+        .into_template_response());
     }
 
-    // Apply ordering if specified
-    let mut final_sql = sql.to_string();
-    if let Some(ordering) = form.ordering {
-        let direction = if ordering < 0 { "DESC" } else { "ASC" };
-        final_sql = format!(
-            "SELECT * FROM ({}) AS _ ORDER BY {} {}",
-            sql.trim_end_matches(';'),
-            ordering.abs(),
-            direction
-        );
+    if statements.len() > 1 {
+        let template = execute_script(
+            sql,
+            statements,
+            form.transaction,
+            database_info.base_name(),
+            schema.prefix(),
+            &manager,
+        )
+        .await;
+        return Ok(template.into_template_response());
     }
+    let sql = statements[0].as_str();
 
-    // Apply pagination for SELECT queries
-    let is_select = sql.trim_start().to_uppercase().starts_with("SELECT");
-    let mut total_rows = None;
-
-    if is_select && page > 1 {
-        // Get total count for pagination
-        let count_sql = format!(
-            "SELECT COUNT(*) as count FROM ({}) AS _",
-            sql.trim_end_matches(';')
-        );
-        match manager.execute_query(&count_sql).await {
-            Ok(count_result) => {
-                if let Some(first_row) = count_result.rows.first() {
-                    if let Some(Some(count_str)) = first_row.first() {
-                        total_rows = count_str.parse::<i64>().ok();
-                    }
-                }
-            }
-            Err(_) => {
-                // If count fails, continue without pagination
-            }
-        }
-
-        // Apply LIMIT and OFFSET
-        let offset = (page - 1) * per_page;
-        final_sql = format!(
-            "SELECT * FROM ({}) AS _ LIMIT {} OFFSET {}",
-            final_sql.trim_end_matches(';'),
-            per_page,
-            offset
-        );
-    }
+    let page = form.page.unwrap_or(1).max(1);
+    let per_page = args.query_rows_per_page.max(1);
+    page_offset(page, per_page).ok_or(Status::BadRequest)?;
 
-    match manager.execute_query(&final_sql).await {
-        Ok(query_result) => {
-            let _total_pages = if let Some(total) = total_rows {
-                cmp::max(1, (total as f64 / per_page as f64).ceil() as usize)
-            } else {
-                1
-            };
+    // Ordering and pagination only apply to queries that can be wrapped.
+    let fetched = if statement::is_query(sql) {
+        manager.fetch_page(sql, page, per_page, form.ordering).await
+    } else {
+        manager
+            .run_statement(sql)
+            .await
+            .map(|result| (result, false))
+    };
 
-            Ok(QueryTemplate::new(
+    match fetched {
+        Ok((query_result, has_more)) => {
+            let mut template = QueryTemplate::new(
                 sql.to_string(),
                 true,
                 query_result.columns,
                 query_result.rows,
                 query_result.rows_affected,
+                query_result.last_insert_id,
                 String::new(),
                 database_info.base_name(),
-            )
-            .into_template_response())
+                schema.prefix(),
+            );
+            template.page = page;
+            template.has_more = has_more;
+            Ok(template.into_template_response())
         }
         Err(e) => Ok(QueryTemplate::new(
             sql.to_string(),
@@ -215,89 +282,194 @@ pub async fn execute_query(
             vec![],
             vec![],
             None,
+            None,
             format!("SQL Error: {}", e),
             database_info.base_name(),
+            schema.prefix(),
         )
         .into_template_response()),
     }
 }
 
+/// Run each statement of a script in turn and give every one its own result.
+async fn execute_script(
+    sql: &str,
+    statements: Vec<String>,
+    use_transaction: bool,
+    database_name: String,
+    schema_prefix: String,
+    manager: &DatabaseManager<'_>,
+) -> QueryTemplate {
+    let mut template = QueryTemplate::new(
+        sql.to_string(),
+        false,
+        vec![],
+        vec![],
+        None,
+        None,
+        String::new(),
+        database_name,
+        schema_prefix,
+    );
+    template.use_transaction = use_transaction;
+
+    let results = match manager.run_script(&statements, use_transaction).await {
+        Ok(results) => results,
+        Err(e) => {
+            template.error = format!("SQL Error: {}", e);
+            return template;
+        }
+    };
+
+    let failed = results.iter().any(|r| r.result.is_err());
+    if failed {
+        template.error = if use_transaction {
+            "Script failed; the transaction was rolled back".to_string()
+        } else {
+            "Script stopped at the first failing statement".to_string()
+        };
+    }
+    template.rolled_back = failed && use_transaction;
+
+    let executed = results.len();
+    template.statements = results
+        .into_iter()
+        .map(StatementOutput::from_result)
+        .chain(
+            statements
+                .into_iter()
+                .skip(executed)
+                .map(StatementOutput::not_executed),
+        )
+        .collect();
+    template.has_result = true;
+    template
+}
+
+/// Upper bound for `total_rows` counts in the query API.
+const API_COUNT_LIMIT: i64 = 100_000;
+
 #[post("/api/query", data = "<request>")]
 pub async fn api_execute_query(
     request: Json<QueryRequest>,
     db: &State<DatabasePool>,
     config: &State<DatabaseConfig>,
+    args: &State<Args>,
     _auth: AuthGuard,
+    schema: Schema,
 ) -> Result<Json<QueryResponse>, Status> {
     let pool = &db.0;
-    let manager = DatabaseManager::new(pool, config.inner().clone());
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
 
-    let sql = request.sql.trim();
-    if sql.is_empty() {
-        return Ok(Json(QueryResponse {
+    let page = request.page.unwrap_or(1).max(1);
+    let per_page = request.per_page.unwrap_or(args.query_rows_per_page).max(1);
+    let error_response = |error: String| {
+        Json(QueryResponse {
             columns: vec![],
             rows: vec![],
             total_rows: None,
-            page: 1,
-            per_page: 1000,
+            page,
+            per_page,
             total_pages: 1,
-            error: Some("SQL query cannot be empty".to_string()),
+            error: Some(error),
             rows_affected: None,
-        }));
+            last_insert_id: None,
+            has_more: false,
+            total_rows_capped: false,
+        })
+    };
+
+    let offset = page_offset(page, per_page).ok_or(Status::BadRequest)?;
+
+    let sql = request.sql.trim();
+    if sql.is_empty() {
+        return Ok(error_response("SQL query cannot be empty".to_string()));
     }
 
     // Check if this is a read-only connection and the query is a write operation
-    if config.readonly && is_write_operation(sql) {
-        return Ok(Json(QueryResponse {
-            columns: vec![],
-            rows: vec![],
-            total_rows: None,
-            page: 1,
-            per_page: 1000,
-            total_pages: 1,
-            error: Some("Write operations are not allowed in read-only mode".to_string()),
-            rows_affected: None,
-        }));
+    if config.readonly && !statement::is_read_only_script(sql, &config.database_type) {
+        return Ok(error_response(
+            "Write operations are not allowed in read-only mode".to_string(),
+        ));
     }
 
-    let page = request.page.unwrap_or(1);
-    let per_page = request.per_page.unwrap_or(1000);
-
-    match manager.execute_query(sql).await {
-        Ok(query_result) => {
-            let total_pages = 1; // TODO: Implement proper pagination for API
-
-            Ok(Json(QueryResponse {
-                columns: query_result.columns,
-                rows: query_result.rows,
+    // Statements without a result set only report how many rows they changed.
+    if !statement::returns_rows(sql) {
+        return Ok(match manager.execute_statement(sql).await {
+            Ok(result) => Json(QueryResponse {
+                columns: vec![],
+                rows: vec![],
                 total_rows: None,
-                page,
+                page: 1,
                 per_page,
-                total_pages,
+                total_pages: 1,
                 error: None,
-                rows_affected: query_result.rows_affected,
-            }))
+                rows_affected: result.rows_affected,
+                last_insert_id: result.last_insert_id,
+                has_more: false,
+                total_rows_capped: false,
+            }),
+            Err(e) => error_response(format!("SQL Error: {}", e)),
+        });
+    }
+
+    let fetched = if statement::is_query(sql) {
+        manager
+            .fetch_page(sql, page, per_page, request.ordering)
+            .await
+            .map(|(result, has_more)| (result, has_more, None))
+    } else {
+        // SHOW, PRAGMA and the like can't be wrapped in a subquery, so they
+        // are fetched whole and paginated here.
+        manager.execute_query(sql).await.map(|mut result| {
+            let total = result.rows.len();
+            result.rows = result
+                .rows
+                .into_iter()
+                .skip(offset)
+                .take(per_page)
+                .collect();
+            (result, offset + per_page < total, Some(total as i64))
+        })
+    };
+
+    let (query_result, has_more, known_total) = match fetched {
+        Ok(fetched) => fetched,
+        Err(e) => return Ok(error_response(format!("SQL Error: {}", e))),
+    };
+
+    let mut total_rows = known_total;
+    let mut total_rows_capped = false;
+    if total_rows.is_none() && request.count.unwrap_or(true) {
+        // A failed count only loses the total; the page itself is still valid.
+        if let Ok(count) = manager.count_rows(sql, API_COUNT_LIMIT).await {
+            total_rows_capped = count > API_COUNT_LIMIT;
+            total_rows = Some(count.min(API_COUNT_LIMIT));
         }
-        Err(e) => Ok(Json(QueryResponse {
-            columns: vec![],
-            rows: vec![],
-            total_rows: None,
-            page,
-            per_page,
-            total_pages: 1,
-            error: Some(format!("SQL Error: {}", e)),
-            rows_affected: None,
-        })),
     }
-}
 
-fn is_write_operation(sql: &str) -> bool {
-    let sql_upper = sql.trim().to_uppercase();
-    sql_upper.starts_with("INSERT")
-        || sql_upper.starts_with("UPDATE")
-        || sql_upper.starts_with("DELETE")
-        || sql_upper.starts_with("DROP")
-        || sql_upper.starts_with("CREATE")
-        || sql_upper.starts_with("ALTER")
-        || sql_upper.starts_with("TRUNCATE")
+    // Without a total, the last known page is the next one if more rows
+    // follow. A capped total only gives a lower bound, as flagged.
+    let known_pages = page + has_more as usize;
+    let total_pages = match total_rows {
+        Some(total) if total_rows_capped => {
+            cmp::max(known_pages, (total as usize).div_ceil(per_page))
+        }
+        Some(total) => cmp::max(1, (total as usize).div_ceil(per_page)),
+        None => known_pages,
+    };
+
+    Ok(Json(QueryResponse {
+        columns: query_result.columns,
+        rows: query_result.rows,
+        total_rows,
+        page,
+        per_page,
+        total_pages,
+        error: None,
+        rows_affected: query_result.rows_affected,
+        last_insert_id: query_result.last_insert_id,
+        has_more,
+        total_rows_capped,
+    }))
 }
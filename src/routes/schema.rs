@@ -0,0 +1,82 @@
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::uri::Origin;
+use rocket::http::RawStr;
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::Data;
+
+/// The Postgres schema being browsed, taken from a `/schema/<name>` prefix
+/// on the request path. `None` follows the search_path.
+#[derive(Debug, Clone)]
+pub struct Schema(pub Option<String>);
+
+impl Schema {
+    /// Path prefix that keeps links within the schema being browsed.
+    pub fn prefix(&self) -> String {
+        match &self.0 {
+            Some(schema) => format!("/schema/{}", RawStr::new(schema).percent_encode()),
+            None => String::new(),
+        }
+    }
+
+    /// Prefix `path` so it stays within the schema being browsed.
+    pub fn path(&self, path: &str) -> String {
+        format!("{}{}", self.prefix(), path)
+    }
+
+    /// Path of `page` (such as `content`) of `table` in this schema, with the
+    /// table name percent-encoded as a path segment.
+    pub fn table_path(&self, table: &str, page: &str) -> String {
+        self.path(&format!(
+            "/table/{}/{}",
+            RawStr::new(table).percent_encode(),
+            page
+        ))
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Schema {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(request.local_cache(|| Schema(None)).clone())
+    }
+}
+
+/// Strips the `/schema/<name>` prefix before routing, so every route serves
+/// both the default schema and any named one.
+pub struct SchemaPrefix;
+
+#[rocket::async_trait]
+impl Fairing for SchemaPrefix {
+    fn info(&self) -> Info {
+        Info {
+            name: "Schema prefix",
+            kind: Kind::Request,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut Data<'_>) {
+        let path = request.uri().path().as_str();
+        let Some(rest) = path.strip_prefix("/schema/") else {
+            return;
+        };
+        let (schema, rest) = match rest.find('/') {
+            Some(end) => (&rest[..end], &rest[end..]),
+            None => (rest, "/"),
+        };
+        let schema = match RawStr::new(schema).percent_decode() {
+            Ok(schema) if !schema.is_empty() => schema.into_owned(),
+            _ => return,
+        };
+
+        let uri = match request.uri().query() {
+            Some(query) => format!("{}?{}", rest, query),
+            None => rest.to_string(),
+        };
+        if let Ok(origin) = Origin::parse_owned(uri) {
+            request.local_cache(|| Schema(Some(schema)));
+            request.set_uri(origin);
+        }
+    }
+}
@@ -0,0 +1,444 @@
+use crate::config::{DatabaseConfig, DatabaseManager, DatabasePool};
+use crate::models::{CreateColumnRequest, CreateTableRequest, FlashMessage as Flash};
+use crate::routes::index::AuthGuard;
+use crate::routes::schema::Schema;
+use crate::template::{IntoTemplateResponse, TemplateResponse};
+use askama::Template;
+use rocket::form::Form;
+use rocket::http::Status;
+use rocket::response::Redirect;
+use rocket::{get, post, State};
+
+#[derive(Template)]
+#[template(path = "create_table.html")]
+pub struct CreateTableTemplate {
+    pub table_name: String,
+    pub columns: Vec<CreateColumnRequest>,
+    pub column_types: Vec<String>,
+    // DDL shown when previewing, or the statement that failed.
+    pub sql: String,
+    pub database_name: String,
+    pub schema_prefix: String,
+    pub readonly: bool,
+    pub flash_messages: Vec<Flash>,
+    pub version: String,
+    pub error: String,
+}
+
+#[derive(Template)]
+#[template(path = "rename_table.html")]
+pub struct RenameTableTemplate {
+    pub table_name: String,
+    pub new_name: String,
+    pub database_name: String,
+    pub schema_prefix: String,
+    pub readonly: bool,
+    pub flash_messages: Vec<Flash>,
+    pub version: String,
+    pub error: String,
+}
+
+#[derive(Template)]
+#[template(path = "duplicate_table.html")]
+pub struct DuplicateTableTemplate {
+    pub table_name: String,
+    pub new_name: String,
+    pub with_data: bool,
+    pub database_name: String,
+    pub schema_prefix: String,
+    pub readonly: bool,
+    pub flash_messages: Vec<Flash>,
+    pub version: String,
+    pub error: String,
+}
+
+#[derive(rocket::FromForm)]
+pub struct CreateTableForm {
+    pub table_name: String,
+    pub columns: Vec<ColumnForm>,
+    // Set by the Preview button: show the DDL instead of running it.
+    pub preview: bool,
+}
+
+#[derive(rocket::FromForm)]
+pub struct ColumnForm {
+    pub name: Option<String>,
+    pub data_type: Option<String>,
+    pub nullable: bool,
+    pub default_value: Option<String>,
+    pub primary_key: bool,
+    pub auto_increment: bool,
+    pub unique: bool,
+}
+
+#[derive(rocket::FromForm)]
+pub struct RenameTableForm {
+    pub new_name: String,
+}
+
+#[derive(rocket::FromForm)]
+pub struct DuplicateTableForm {
+    pub new_name: String,
+    pub with_data: bool,
+}
+
+impl From<&ColumnForm> for CreateColumnRequest {
+    fn from(form: &ColumnForm) -> Self {
+        // Auto-increment columns are always the primary key.
+        let auto_increment = form.auto_increment;
+        CreateColumnRequest {
+            name: form.name.clone().unwrap_or_default().trim().to_string(),
+            data_type: form
+                .data_type
+                .clone()
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+            nullable: form.nullable && !form.primary_key && !auto_increment,
+            default_value: form.default_value.clone().filter(|v| !v.is_empty()),
+            primary_key: form.primary_key || auto_increment,
+            auto_increment,
+            unique: form.unique,
+        }
+    }
+}
+
+fn blank_column(data_type: &str) -> CreateColumnRequest {
+    CreateColumnRequest {
+        name: String::new(),
+        data_type: data_type.to_string(),
+        nullable: true,
+        default_value: None,
+        primary_key: false,
+        auto_increment: false,
+        unique: false,
+    }
+}
+
+#[get("/create-table?<table_name>")]
+pub async fn create_table(
+    table_name: Option<String>,
+    db: &State<DatabasePool>,
+    config: &State<DatabaseConfig>,
+    _auth: AuthGuard,
+    schema: Schema,
+) -> Result<TemplateResponse<CreateTableTemplate>, Status> {
+    if config.readonly {
+        return Err(Status::Forbidden);
+    }
+
+    let pool = &db.0;
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
+
+    let database_info = manager
+        .get_database_info()
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    // Start from an auto-increment id column and an empty one.
+    let column_types = config.database_type.column_types();
+    let id = CreateColumnRequest {
+        name: "id".to_string(),
+        nullable: false,
+        primary_key: true,
+        auto_increment: true,
+        ..blank_column(column_types[0])
+    };
+
+    Ok(CreateTableTemplate {
+        table_name: table_name.unwrap_or_default().trim().to_string(),
+        columns: vec![id, blank_column("")],
+        column_types: column_types.iter().map(|t| t.to_string()).collect(),
+        sql: String::new(),
+        database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
+        readonly: database_info.readonly,
+        flash_messages: vec![],
+        version: "0.1.0".to_string(),
+        error: String::new(),
+    }
+    .into_template_response())
+}
+
+#[post("/create-table", data = "<form>")]
+pub async fn create_table_execute(
+    form: Form<CreateTableForm>,
+    db: &State<DatabasePool>,
+    config: &State<DatabaseConfig>,
+    _auth: AuthGuard,
+    schema: Schema,
+) -> Result<Result<Redirect, TemplateResponse<CreateTableTemplate>>, Status> {
+    if config.readonly {
+        return Err(Status::Forbidden);
+    }
+
+    let pool = &db.0;
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
+
+    let database_info = manager
+        .get_database_info()
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    // Rows left without a name are unused.
+    let request = CreateTableRequest {
+        name: manager.qualify(form.table_name.trim()),
+        columns: form
+            .columns
+            .iter()
+            .map(CreateColumnRequest::from)
+            .filter(|c| !c.name.is_empty())
+            .collect(),
+    };
+
+    let mut sql = String::new();
+    let error = if request.name.name.is_empty() {
+        "The table needs a name".to_string()
+    } else if request.columns.is_empty() {
+        "The table needs at least one column".to_string()
+    } else if let Some(column) = request.columns.iter().find(|c| c.data_type.is_empty()) {
+        format!("Column {} needs a type", column.name)
+    } else {
+        sql = manager.create_table_sql(&request);
+        if form.preview {
+            String::new()
+        } else {
+            match manager.create_table(&request).await {
+                Ok(()) => {
+                    return Ok(Ok(Redirect::to(
+                        schema.table_path(&request.name.name, "structure"),
+                    )));
+                }
+                Err(e) => format!("SQL Error: {}", e),
+            }
+        }
+    };
+
+    let column_types = config.database_type.column_types();
+    let mut columns = request.columns;
+    if columns.is_empty() {
+        columns.push(blank_column(""));
+    }
+
+    Ok(Err(CreateTableTemplate {
+        table_name: request.name.name,
+        columns,
+        column_types: column_types.iter().map(|t| t.to_string()).collect(),
+        sql,
+        database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
+        readonly: database_info.readonly,
+        flash_messages: vec![],
+        version: "0.1.0".to_string(),
+        error,
+    }
+    .into_template_response()))
+}
+
+#[get("/table/<table_name>/rename")]
+pub async fn rename_table(
+    table_name: String,
+    db: &State<DatabasePool>,
+    config: &State<DatabaseConfig>,
+    _auth: AuthGuard,
+    schema: Schema,
+) -> Result<TemplateResponse<RenameTableTemplate>, Status> {
+    if config.readonly {
+        return Err(Status::Forbidden);
+    }
+
+    let pool = &db.0;
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
+
+    let database_info = manager
+        .get_database_info()
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    Ok(RenameTableTemplate {
+        new_name: table_name.clone(),
+        table_name,
+        database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
+        readonly: database_info.readonly,
+        flash_messages: vec![],
+        version: "0.1.0".to_string(),
+        error: String::new(),
+    }
+    .into_template_response())
+}
+
+#[post("/table/<table_name>/rename", data = "<form>")]
+pub async fn rename_table_execute(
+    table_name: String,
+    form: Form<RenameTableForm>,
+    db: &State<DatabasePool>,
+    config: &State<DatabaseConfig>,
+    _auth: AuthGuard,
+    schema: Schema,
+) -> Result<Result<Redirect, TemplateResponse<RenameTableTemplate>>, Status> {
+    if config.readonly {
+        return Err(Status::Forbidden);
+    }
+
+    let pool = &db.0;
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
+
+    let database_info = manager
+        .get_database_info()
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    let new_name = form.new_name.trim().to_string();
+    let error = if new_name.is_empty() {
+        "The table needs a name".to_string()
+    } else {
+        match manager
+            .rename_table(&manager.qualify(&table_name), &new_name)
+            .await
+        {
+            Ok(()) => {
+                return Ok(Ok(Redirect::to(schema.table_path(&new_name, "structure"))));
+            }
+            Err(e) => format!("SQL Error: {}", e),
+        }
+    };
+
+    Ok(Err(RenameTableTemplate {
+        table_name,
+        new_name,
+        database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
+        readonly: database_info.readonly,
+        flash_messages: vec![],
+        version: "0.1.0".to_string(),
+        error,
+    }
+    .into_template_response()))
+}
+
+#[get("/table/<table_name>/duplicate")]
+pub async fn duplicate_table(
+    table_name: String,
+    db: &State<DatabasePool>,
+    config: &State<DatabaseConfig>,
+    _auth: AuthGuard,
+    schema: Schema,
+) -> Result<TemplateResponse<DuplicateTableTemplate>, Status> {
+    if config.readonly {
+        return Err(Status::Forbidden);
+    }
+
+    let pool = &db.0;
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
+
+    let database_info = manager
+        .get_database_info()
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    Ok(DuplicateTableTemplate {
+        new_name: format!("{}_copy", table_name),
+        table_name,
+        with_data: false,
+        database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
+        readonly: database_info.readonly,
+        flash_messages: vec![],
+        version: "0.1.0".to_string(),
+        error: String::new(),
+    }
+    .into_template_response())
+}
+
+#[post("/table/<table_name>/duplicate", data = "<form>")]
+pub async fn duplicate_table_execute(
+    table_name: String,
+    form: Form<DuplicateTableForm>,
+    db: &State<DatabasePool>,
+    config: &State<DatabaseConfig>,
+    _auth: AuthGuard,
+    schema: Schema,
+) -> Result<Result<Redirect, TemplateResponse<DuplicateTableTemplate>>, Status> {
+    if config.readonly {
+        return Err(Status::Forbidden);
+    }
+
+    let pool = &db.0;
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
+
+    let database_info = manager
+        .get_database_info()
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    let new_name = form.new_name.trim().to_string();
+    let error = if new_name.is_empty() {
+        "The copy needs a name".to_string()
+    } else {
+        match manager
+            .duplicate_table(&manager.qualify(&table_name), &new_name, form.with_data)
+            .await
+        {
+            Ok(()) => {
+                return Ok(Ok(Redirect::to(schema.table_path(&new_name, "structure"))));
+            }
+            Err(e) => format!("SQL Error: {}", e),
+        }
+    };
+
+    Ok(Err(DuplicateTableTemplate {
+        table_name,
+        new_name,
+        with_data: form.with_data,
+        database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
+        readonly: database_info.readonly,
+        flash_messages: vec![],
+        version: "0.1.0".to_string(),
+        error,
+    }
+    .into_template_response()))
+}
+
+#[post("/table/<table_name>/truncate")]
+pub async fn truncate_table_execute(
+    table_name: String,
+    db: &State<DatabasePool>,
+    config: &State<DatabaseConfig>,
+    _auth: AuthGuard,
+    schema: Schema,
+) -> Result<Redirect, Status> {
+    if config.readonly {
+        return Err(Status::Forbidden);
+    }
+
+    let pool = &db.0;
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
+
+    match manager.truncate_table(&manager.qualify(&table_name)).await {
+        Ok(_) => Ok(Redirect::to(schema.table_path(&table_name, "content"))),
+        Err(_) => Err(Status::InternalServerError),
+    }
+}
+
+#[post("/table/<table_name>/drop")]
+pub async fn drop_table_execute(
+    table_name: String,
+    db: &State<DatabasePool>,
+    config: &State<DatabaseConfig>,
+    _auth: AuthGuard,
+    schema: Schema,
+) -> Result<Redirect, Status> {
+    if config.readonly {
+        return Err(Status::Forbidden);
+    }
+
+    let pool = &db.0;
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
+
+    match manager.drop_table(&manager.qualify(&table_name)).await {
+        Ok(_) => Ok(Redirect::to(schema.path("/tables"))),
+        Err(_) => Err(Status::InternalServerError),
+    }
+}
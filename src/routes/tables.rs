@@ -1,22 +1,39 @@
-use crate::config::{DatabaseConfig, DatabaseManager, DatabasePool};
+use crate::config::{
+    page_offset, ColumnInfo, DatabaseConfig, DatabaseManager, DatabasePool, DatabaseType,
+    ForeignKeyInfo, QueryResult, RowIdentity, RowUpdate, TableInfo,
+};
+use crate::export::{build_export_sql, export_download, ExportDownload, ExportFormat};
+use crate::import::{import_rows, infer_column_type, match_column, parse_import};
 use crate::models::{
-    FlashMessage as Flash, PaginationInfo, TableData, TableStructure, TableStructureForTemplate,
+    row_version, AddColumnRequest, CellValue, ColumnFilter, ColumnValue, CreateColumnRequest,
+    DeleteRequest, ExportRequest, FilterOperator, FlashMessage as Flash, ForeignKeyDetail,
+    ImportRequest, ImportRowError, InsertRequest, PaginationInfo, TableData, TableStructure,
+    TableStructureForTemplate, TriggerDetail, UpdateRequest, ViewInfo,
 };
+use crate::query_builder;
 use crate::routes::index::AuthGuard;
+use crate::routes::schema::Schema;
+use crate::statement;
 use crate::template::{IntoTemplateResponse, TemplateResponse};
 use crate::Args;
 use askama::Template;
 use rocket::form::Form;
+use rocket::fs::TempFile;
 use rocket::http::Status;
 use rocket::response::Redirect;
 use rocket::{get, post, State};
 use std::collections::HashMap;
+use tokio::io::AsyncReadExt;
 
 #[derive(Template)]
 #[template(path = "table_list.html")]
 pub struct TableListTemplate {
     pub tables: Vec<String>,
+    pub views: Vec<ViewInfo>,
+    pub schemas: Vec<String>,
+    pub current_schema: String,
     pub database_name: String,
+    pub schema_prefix: String,
     pub readonly: bool,
     pub flash_messages: Vec<Flash>,
     pub version: String,
@@ -27,6 +44,7 @@ pub struct TableListTemplate {
 pub struct TableStructureTemplate {
     pub table: TableStructureForTemplate,
     pub database_name: String,
+    pub schema_prefix: String,
     pub readonly: bool,
     pub flash_messages: Vec<Flash>,
     pub version: String,
@@ -36,8 +54,25 @@ pub struct TableStructureTemplate {
 #[template(path = "table_content.html")]
 pub struct TableContentTemplate {
     pub table_data: crate::models::TableDataForTemplate,
+    pub row_keys: Vec<String>,
+    // Per-row, per-column link to the row a foreign key cell references, or
+    // empty.
+    pub fk_links: Vec<Vec<String>>,
+    // Whether rows can be addressed individually, to edit or delete them.
+    pub can_edit_rows: bool,
     pub pagination: PaginationInfo,
+    pub sort_column: String,
+    pub sort_desc: bool,
+    // Per-column links sorting the grid by that column, keeping the filters.
+    pub sort_links: Vec<String>,
+    // Query string to append to pagination links.
+    pub page_query: String,
+    pub filter_fields: Vec<FilterField>,
+    pub filter_operators: Vec<(&'static str, &'static str)>,
+    pub has_filters: bool,
+    pub is_view: bool,
     pub database_name: String,
+    pub schema_prefix: String,
     pub readonly: bool,
     pub flash_messages: Vec<Flash>,
     pub version: String,
@@ -51,11 +86,13 @@ pub struct TableQueryTemplate {
     pub sql: String,
     pub has_result: bool,
     pub columns: Vec<String>,
-    pub rows: Vec<Vec<String>>,
+    pub rows: Vec<Vec<CellValue>>,
     pub rows_affected_display: String,
+    pub last_insert_id_display: String,
     pub total_rows: Option<i64>,
     pub error: String,
     pub database_name: String,
+    pub schema_prefix: String,
     pub readonly: bool,
     pub flash_messages: Vec<Flash>,
     pub version: String,
@@ -65,36 +102,34 @@ pub struct TableQueryTemplate {
 }
 
 impl TableQueryTemplate {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         table_name: String,
         sql: String,
         has_result: bool,
         columns: Vec<String>,
-        rows: Vec<Vec<Option<String>>>,
+        rows: Vec<Vec<CellValue>>,
         rows_affected: Option<u64>,
+        last_insert_id: Option<i64>,
         total_rows: Option<i64>,
         error: String,
         database_name: String,
+        schema_prefix: String,
         readonly: bool,
         flash_messages: Vec<Flash>,
         version: String,
     ) -> Self {
         let rows_affected_display = match rows_affected {
             Some(count) => count.to_string(),
-            None => "0".to_string(),
+            None => String::new(),
+        };
+        let last_insert_id_display = match last_insert_id {
+            Some(id) => id.to_string(),
+            None => String::new(),
         };
-
-        let processed_rows: Vec<Vec<String>> = rows
-            .into_iter()
-            .map(|row| {
-                row.into_iter()
-                    .map(|cell| cell.unwrap_or_else(|| "NULL".to_string()))
-                    .collect()
-            })
-            .collect();
 
         let has_columns = !columns.is_empty();
-        let has_rows = !processed_rows.is_empty();
+        let has_rows = !rows.is_empty();
         let has_total_rows = total_rows.is_some();
 
         Self {
@@ -102,11 +137,13 @@ impl TableQueryTemplate {
             sql,
             has_result,
             columns,
-            rows: processed_rows,
+            rows,
             rows_affected_display,
+            last_insert_id_display,
             total_rows,
             error,
             database_name,
+            schema_prefix,
             readonly,
             flash_messages,
             version,
@@ -117,12 +154,56 @@ impl TableQueryTemplate {
     }
 }
 
+/// A column's field on the insert and edit forms.
+pub struct RowField {
+    pub column: ColumnInfo,
+    // See `ColumnInfo::input_kind`, or `text` when the current value doesn't
+    // fit the typed widget.
+    pub kind: &'static str,
+    // Current value in the widget's format; for blobs a note of its size.
+    pub value: String,
+    pub is_null: bool,
+    pub use_default: bool,
+    pub readonly: bool,
+}
+
+impl RowField {
+    pub fn new(column: ColumnInfo, value: Option<String>) -> Self {
+        let mut kind = column.input_kind();
+        if let Some(value) = &value
+            && kind != "blob"
+            && !column.input_accepts(value)
+        {
+            kind = "text";
+        }
+        let is_null = value.is_none();
+        let mut value = value.unwrap_or_default();
+        if kind == "datetime" {
+            value = value.replacen(' ', "T", 1);
+        }
+        Self {
+            column,
+            kind,
+            value,
+            is_null,
+            use_default: false,
+            readonly: false,
+        }
+    }
+
+    // Whether "use default" means more than NULL.
+    pub fn has_default(&self) -> bool {
+        self.column.default_value.is_some() || self.column.is_auto_increment
+    }
+}
+
 #[derive(Template)]
 #[template(path = "table_insert.html")]
 pub struct TableInsertTemplate {
     pub table_name: String,
-    pub columns: Vec<crate::config::ColumnInfo>,
+    pub fields: Vec<RowField>,
     pub database_name: String,
+    pub schema_prefix: String,
     pub readonly: bool,
     pub flash_messages: Vec<Flash>,
     pub version: String,
@@ -133,62 +214,52 @@ pub struct TableInsertTemplate {
 #[template(path = "table_update.html")]
 pub struct TableUpdateTemplate {
     pub table_name: String,
-    pub columns: Vec<crate::config::ColumnInfo>,
-    pub row_values: Vec<String>,
-    pub primary_key_values: Vec<String>,
+    pub fields: Vec<RowField>,
+    // Columns and values identifying the row being edited.
+    pub key_values: Vec<(String, String)>,
+    // See `row_version`.
+    pub row_version: String,
     pub database_name: String,
+    pub schema_prefix: String,
     pub readonly: bool,
     pub flash_messages: Vec<Flash>,
     pub version: String,
     pub error: String,
 }
 
-impl TableUpdateTemplate {
-    pub fn new(
-        table_name: String,
-        columns: Vec<crate::config::ColumnInfo>,
-        row_data: HashMap<String, Option<String>>,
-        primary_key: HashMap<String, String>,
-        database_name: String,
-        readonly: bool,
-        flash_messages: Vec<Flash>,
-        version: String,
-        error: String,
-    ) -> Self {
-        let row_values: Vec<String> = columns
-            .iter()
-            .map(|col| {
-                row_data
-                    .get(&col.name)
-                    .and_then(|v| v.as_ref())
-                    .unwrap_or(&"NULL".to_string())
-                    .clone()
-            })
-            .collect();
+/// A column of the row conflict page.
+pub struct ConflictColumn {
+    pub name: String,
+    pub current: String,
+    pub submitted: String,
+    pub differs: bool,
+}
 
-        let primary_key_values: Vec<String> = columns
-            .iter()
-            .filter(|col| col.is_primary_key)
-            .map(|col| {
-                primary_key
-                    .get(&col.name)
-                    .unwrap_or(&"".to_string())
-                    .clone()
-            })
-            .collect();
+#[derive(Template)]
+#[template(path = "row_conflict.html")]
+pub struct RowConflictTemplate {
+    pub table_name: String,
+    // Empty when the row no longer exists.
+    pub columns: Vec<ConflictColumn>,
+    pub key_values: Vec<(String, String)>,
+    // The submitted form fields, posted again to save over the changes.
+    pub resubmit: Vec<(String, String)>,
+    pub row_version: String,
+    pub has_upload: bool,
+    pub database_name: String,
+    pub schema_prefix: String,
+    pub readonly: bool,
+    pub flash_messages: Vec<Flash>,
+    pub version: String,
+}
 
-        Self {
-            table_name,
-            columns,
-            row_values,
-            primary_key_values,
-            database_name,
-            readonly,
-            flash_messages,
-            version,
-            error,
-        }
-    }
+/// Responses to the row edit form.
+#[derive(rocket::Responder)]
+pub enum UpdateResponse {
+    Saved(Redirect),
+    Failed(TemplateResponse<TableUpdateTemplate>),
+    #[response(status = 409)]
+    Conflict(TemplateResponse<RowConflictTemplate>),
 }
 
 #[derive(Template)]
@@ -197,9 +268,11 @@ pub struct TableExportTemplate {
     pub table_name: String,
     pub columns: Vec<String>,
     pub database_name: String,
+    pub schema_prefix: String,
     pub readonly: bool,
     pub flash_messages: Vec<Flash>,
     pub version: String,
+    pub error: String,
 }
 
 #[derive(Template)]
@@ -208,10 +281,12 @@ pub struct TableImportTemplate {
     pub table_name: String,
     pub columns: Vec<String>,
     pub database_name: String,
+    pub schema_prefix: String,
     pub readonly: bool,
     pub flash_messages: Vec<Flash>,
     pub version: String,
     pub error: String,
+    pub row_errors: Vec<ImportRowError>,
 }
 
 #[derive(rocket::FromForm)]
@@ -220,17 +295,111 @@ pub struct QueryForm {
 }
 
 #[derive(rocket::FromForm)]
-pub struct InsertForm {
+pub struct ExportForm {
+    format: String,
+    columns: Vec<String>,
+    where_clause: Option<String>,
+    limit: Option<usize>,
+}
+
+#[derive(rocket::FromForm)]
+pub struct ImportForm<'r> {
+    format: String,
+    import_file: Option<TempFile<'r>>,
+    import_data: Option<String>,
+    create_columns: Option<bool>,
+    ignore_errors: Option<bool>,
+}
+
+#[derive(rocket::FromForm)]
+pub struct DeleteForm {
+    // Each entry is a URL-encoded set of key column/value pairs.
+    pub keys: Vec<String>,
+}
+
+#[derive(rocket::FromForm)]
+pub struct InsertForm<'r> {
     #[field(name = "data")]
     data: HashMap<String, String>,
+    null: HashMap<String, bool>,
+    default: HashMap<String, bool>,
+    file: HashMap<String, TempFile<'r>>,
 }
 
 #[derive(rocket::FromForm)]
-pub struct UpdateForm {
+pub struct UpdateForm<'r> {
     #[field(name = "data")]
     data: HashMap<String, String>,
+    null: HashMap<String, bool>,
+    default: HashMap<String, bool>,
+    file: HashMap<String, TempFile<'r>>,
     #[field(name = "pk")]
     pk: HashMap<String, String>,
+    row_version: String,
+}
+
+/// Values submitted through the insert or edit form. The NULL and "use
+/// default" toggles win over the value field. Blobs without an upload and
+/// generated columns are left out, so an edit keeps their current value.
+async fn row_values(
+    columns: &[ColumnInfo],
+    data: &HashMap<String, String>,
+    null: &HashMap<String, bool>,
+    default: &HashMap<String, bool>,
+    files: &HashMap<String, TempFile<'_>>,
+) -> std::io::Result<HashMap<String, ColumnValue>> {
+    let mut values = HashMap::new();
+    for column in columns.iter().filter(|c| c.generated.is_none()) {
+        let name = &column.name;
+        let value = if default.get(name).copied().unwrap_or(false) {
+            ColumnValue::Default
+        } else if null.get(name).copied().unwrap_or(false) {
+            ColumnValue::Value(CellValue::Null)
+        } else if column.input_kind() == "blob" {
+            let Some(file) = files.get(name).filter(|f| f.len() > 0) else {
+                continue;
+            };
+            let mut bytes = Vec::new();
+            file.open().await?.read_to_end(&mut bytes).await?;
+            ColumnValue::Value(CellValue::Bytes(bytes))
+        } else {
+            // An unchecked checkbox sends nothing, which reads as false.
+            let input = data.get(name).map_or("", String::as_str);
+            ColumnValue::Value(column.parse_input(input))
+        };
+        values.insert(name.clone(), value);
+    }
+    Ok(values)
+}
+
+/// `map` as pairs in key order, so hidden fields render stably.
+fn sorted_pairs(map: &HashMap<String, String>) -> Vec<(String, String)> {
+    let mut pairs: Vec<(String, String)> = map.clone().into_iter().collect();
+    pairs.sort();
+    pairs
+}
+
+/// Fields holding what was submitted, to show the form again after an error.
+/// Uploaded files are not kept.
+fn submitted_fields(
+    columns: Vec<ColumnInfo>,
+    data: &HashMap<String, String>,
+    null: &HashMap<String, bool>,
+    default: &HashMap<String, bool>,
+) -> Vec<RowField> {
+    columns
+        .into_iter()
+        .map(|column| {
+            let value = data.get(&column.name).cloned().unwrap_or_default();
+            let is_null = null.get(&column.name).copied().unwrap_or(false);
+            let use_default = default.get(&column.name).copied().unwrap_or(false);
+            RowField {
+                is_null,
+                use_default,
+                ..RowField::new(column, Some(value))
+            }
+        })
+        .collect()
 }
 
 #[get("/tables")]
@@ -238,9 +407,10 @@ pub async fn table_list(
     db: &State<DatabasePool>,
     config: &State<DatabaseConfig>,
     _auth: AuthGuard,
+    schema: Schema,
 ) -> Result<TemplateResponse<TableListTemplate>, Status> {
     let pool = &db.0;
-    let manager = DatabaseManager::new(pool, config.inner().clone());
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
 
     let database_info = manager
         .get_database_info()
@@ -252,9 +422,17 @@ pub async fn table_list(
         .await
         .map_err(|_| Status::InternalServerError)?;
 
+    let views = manager.get_views().await.unwrap_or_default();
+    let schemas = manager.get_schemas().await.unwrap_or_default();
+    let current_schema = manager.current_schema().await.ok().flatten();
+
     Ok(TableListTemplate {
         tables,
+        views,
+        schemas,
+        current_schema: current_schema.unwrap_or_default(),
         database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
         readonly: database_info.readonly,
         flash_messages: vec![],
         version: "0.1.0".to_string(),
@@ -268,9 +446,10 @@ pub async fn table_structure(
     db: &State<DatabasePool>,
     config: &State<DatabaseConfig>,
     _auth: AuthGuard,
+    schema: Schema,
 ) -> Result<TemplateResponse<TableStructureTemplate>, Status> {
     let pool = &db.0;
-    let manager = DatabaseManager::new(pool, config.inner().clone());
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
 
     let database_info = manager
         .get_database_info()
@@ -278,12 +457,12 @@ pub async fn table_structure(
         .map_err(|_| Status::InternalServerError)?;
 
     let table_info = manager
-        .get_table_info(&table_name)
+        .get_table_info(&manager.qualify(&table_name))
         .await
         .map_err(|_| Status::InternalServerError)?;
 
     let indexes = manager
-        .get_indexes(&table_name)
+        .get_indexes(&manager.qualify(&table_name))
         .await
         .unwrap_or_else(|_| vec![]);
 
@@ -297,8 +476,13 @@ pub async fn table_structure(
             nullable: col.nullable,
             default_value: col.default_value,
             is_primary_key: col.is_primary_key,
-            is_auto_increment: false, // TODO: Detect auto increment
-            max_length: None,         // TODO: Extract max length from type
+            is_auto_increment: col.is_auto_increment,
+            max_length: col.max_length,
+            numeric_precision: col.numeric_precision,
+            numeric_scale: col.numeric_scale,
+            collation: col.collation,
+            generated: col.generated,
+            comment: col.comment,
         })
         .collect();
 
@@ -306,24 +490,66 @@ pub async fn table_structure(
         .into_iter()
         .map(|idx| crate::models::IndexDetail {
             name: idx.name,
-            columns: idx.columns,
+            columns: idx.columns.iter().map(|c| c.definition()).collect(),
             unique: idx.unique,
-            index_type: "BTREE".to_string(), // Default type
+            primary: idx.primary,
+            from_constraint: idx.from_constraint,
+            index_type: idx.method,
+            predicate: idx.predicate,
         })
         .collect();
 
+    let foreign_key_detail = |fk: ForeignKeyInfo| ForeignKeyDetail {
+        name: fk.name,
+        table: fk.table,
+        columns: fk.columns,
+        referenced_table: fk.referenced_table,
+        referenced_columns: fk.referenced_columns,
+        on_delete: fk.on_delete,
+        on_update: fk.on_update,
+    };
+    let foreign_keys = manager
+        .get_foreign_keys(&manager.qualify(&table_name))
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(foreign_key_detail)
+        .collect();
+    let referenced_by = manager
+        .get_referencing_foreign_keys(&manager.qualify(&table_name))
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(foreign_key_detail)
+        .collect();
+
+    let triggers = manager
+        .get_triggers(Some(&manager.qualify(&table_name)))
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(TriggerDetail::from)
+        .collect();
+
+    let create_sql = manager
+        .get_create_table_sql(&manager.qualify(&table_name))
+        .await
+        .ok();
+
     let table = TableStructure {
         name: table_name,
         columns,
         indexes: index_details,
-        foreign_keys: vec![], // TODO: Implement foreign key detection
-        triggers: vec![],     // TODO: Implement trigger detection
-        create_sql: None,     // TODO: Get CREATE TABLE statement
+        foreign_keys,
+        referenced_by,
+        triggers,
+        create_sql,
     };
 
     Ok(TableStructureTemplate {
         table: TableStructureForTemplate::from_table_structure(table),
         database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
         readonly: database_info.readonly,
         flash_messages: vec![],
         version: "0.1.0".to_string(),
@@ -331,44 +557,267 @@ pub async fn table_structure(
     .into_template_response())
 }
 
-#[get("/table/<table_name>/content?<page>&<per_page>")]
+/// Filter on one column of the content grid, given in the query string as
+/// `filter[column].op=contains&filter[column].value=...`.
+#[derive(rocket::FromForm, Default)]
+pub struct FilterForm {
+    op: Option<String>,
+    value: Option<String>,
+    to: Option<String>,
+}
+
+/// Current state of a column's filter, for the filter panel.
+pub struct FilterField {
+    pub column: String,
+    pub op: String,
+    pub value: String,
+    pub to: String,
+}
+
+#[allow(clippy::too_many_arguments)]
+#[get("/table/<table_name>/content?<page>&<per_page>&<sort>&<dir>&<filter>")]
 pub async fn table_content(
     table_name: String,
     page: Option<usize>,
     per_page: Option<usize>,
+    sort: Option<String>,
+    dir: Option<String>,
+    filter: HashMap<String, FilterForm>,
     db: &State<DatabasePool>,
     config: &State<DatabaseConfig>,
     args: &State<Args>,
     _auth: AuthGuard,
+    schema: Schema,
 ) -> Result<TemplateResponse<TableContentTemplate>, Status> {
     let pool = &db.0;
-    let manager = DatabaseManager::new(pool, config.inner().clone());
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
 
     let database_info = manager
         .get_database_info()
         .await
         .map_err(|_| Status::InternalServerError)?;
 
-    let page = page.unwrap_or(1);
-    let per_page = per_page.unwrap_or(args.rows_per_page);
-    let offset = (page - 1) * per_page;
+    let page = page.unwrap_or(1).max(1);
+    let per_page = per_page.unwrap_or(args.rows_per_page).max(1);
+    let offset = page_offset(page, per_page).ok_or(Status::BadRequest)?;
 
-    // Get total row count
-    let total_rows = manager
-        .get_table_row_count(&table_name)
+    let table_info = manager
+        .get_table_info(&manager.qualify(&table_name))
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    let identity = manager
+        .row_identity(&table_info)
         .await
         .map_err(|_| Status::InternalServerError)?;
+    let key_columns = identity.key_columns(&config.database_type);
+
+    // Only columns of the table can be sorted or filtered on.
+    let sort = sort.filter(|column| table_info.columns.iter().any(|c| &c.name == column));
+    let descending = dir.as_deref() == Some("desc");
+    let filters: Vec<ColumnFilter> = table_info
+        .columns
+        .iter()
+        .filter_map(|column| {
+            let form = filter.get(&column.name)?;
+            Some(ColumnFilter {
+                column: column.name.clone(),
+                operator: FilterOperator::parse(form.op.as_deref()?)?,
+                value: form.value.clone(),
+                to: form.to.clone(),
+            })
+        })
+        .collect();
+
+    // Break ties, and order unsorted pages, by the row identity so page
+    // boundaries are stable.
+    let mut order: Vec<(String, bool)> = sort.iter().map(|c| (c.clone(), descending)).collect();
+    let tie_breakers = if key_columns.is_empty() {
+        table_info.columns.iter().map(|c| c.name.clone()).collect()
+    } else {
+        key_columns.clone()
+    };
+    for column in tie_breakers {
+        if !order.iter().any(|(c, _)| *c == column) {
+            order.push((column, false));
+        }
+    }
+
+    // Tables without a primary key are addressed by their physical row id,
+    // which is selected as an extra trailing column.
+    let row_id_select = match identity {
+        RowIdentity::RowId => config
+            .database_type
+            .row_id_column()
+            .map(|(_, _, expression)| format!(", {}", expression))
+            .unwrap_or_default(),
+        _ => String::new(),
+    };
 
-    // Get table data with pagination
-    let sql = format!(
-        "SELECT * FROM \"{}\" LIMIT {} OFFSET {}",
-        table_name, per_page, offset
+    let page_query = query_builder::select_page(
+        &config.database_type,
+        &table_info,
+        &row_id_select,
+        &filters,
+        &order,
+        per_page,
+        offset,
     );
+    let fetched = async {
+        let total_rows = if filters.is_empty() {
+            manager
+                .get_table_row_count(&manager.qualify(&table_name))
+                .await?
+        } else {
+            let count = query_builder::count_filtered(&config.database_type, &table_info, &filters);
+            manager.fetch_count(&count).await?
+        };
+        let query_result = manager.fetch(&page_query).await?;
+        Ok::<_, sqlx::Error>((total_rows, query_result))
+    }
+    .await;
+
+    // A filter value the column's type rejects fails the query; report it
+    // instead of failing the whole page.
+    let mut flash_messages = vec![];
+    let (total_rows, query_result) = match fetched {
+        Ok(fetched) => fetched,
+        Err(e) => {
+            flash_messages.push(Flash {
+                category: "danger".to_string(),
+                message: format!("SQL Error: {}", e),
+            });
+            let empty = QueryResult {
+                columns: table_info.columns.iter().map(|c| c.name.clone()).collect(),
+                rows: vec![],
+                rows_affected: None,
+                last_insert_id: None,
+            };
+            (0, empty)
+        }
+    };
+    let mut query_result = query_result.with_column_types(&table_info);
+
+    let row_ids: Vec<CellValue> = if row_id_select.is_empty() || query_result.rows.is_empty() {
+        vec![]
+    } else {
+        query_result.columns.pop();
+        query_result
+            .rows
+            .iter_mut()
+            .map(|row| row.pop().unwrap_or(CellValue::Null))
+            .collect()
+    };
 
-    let query_result = manager
-        .execute_query(&sql)
+    let row_keys: Vec<String> = query_result
+        .rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            // NULLs only occur in full-row keys, which leave them out.
+            let mut serializer = url::form_urlencoded::Serializer::new(String::new());
+            for key in &key_columns {
+                let value = match row_ids.get(i) {
+                    Some(row_id) => row_id.to_key_value(),
+                    None => query_result
+                        .columns
+                        .iter()
+                        .position(|c| c == key)
+                        .and_then(|index| row[index].to_key_value()),
+                };
+                if let Some(value) = value {
+                    serializer.append_pair(key, &value);
+                }
+            }
+            serializer.finish()
+        })
+        .collect();
+
+    // Cells of a foreign key link to the referenced row, filtered on every
+    // column of the key. Keys with a NULL part don't reference anything.
+    let foreign_keys = manager
+        .get_foreign_keys(&manager.qualify(&table_name))
         .await
-        .map_err(|_| Status::InternalServerError)?;
+        .unwrap_or_default();
+    let fk_links: Vec<Vec<String>> = query_result
+        .rows
+        .iter()
+        .map(|row| {
+            query_result
+                .columns
+                .iter()
+                .map(|column| {
+                    foreign_keys
+                        .iter()
+                        .find(|fk| fk.columns.contains(column))
+                        .and_then(|fk| foreign_key_link(&schema, fk, &query_result.columns, row))
+                        .unwrap_or_default()
+                })
+                .collect()
+        })
+        .collect();
+
+    // Query string carrying the page size and filters, so pagination and
+    // sorting links keep them.
+    let mut state_query = url::form_urlencoded::Serializer::new(String::new());
+    if per_page != args.rows_per_page {
+        state_query.append_pair("per_page", &per_page.to_string());
+    }
+    for filter in &filters {
+        let name = |field: &str| format!("filter[{}].{}", filter.column, field);
+        state_query.append_pair(&name("op"), filter.operator.as_str());
+        if let Some(value) = &filter.value {
+            state_query.append_pair(&name("value"), value);
+        }
+        if let Some(to) = &filter.to {
+            state_query.append_pair(&name("to"), to);
+        }
+    }
+    let state_query = state_query.finish();
+
+    let sort_links = query_result
+        .columns
+        .iter()
+        .map(|column| {
+            let direction = if sort.as_ref() == Some(column) && !descending {
+                "desc"
+            } else {
+                "asc"
+            };
+            let mut link = url::form_urlencoded::Serializer::new(String::new());
+            link.append_pair("sort", column)
+                .append_pair("dir", direction);
+            match state_query.as_str() {
+                "" => format!("?{}", link.finish()),
+                state => format!("?{}&{}", link.finish(), state),
+            }
+        })
+        .collect();
+
+    let mut page_query = state_query.clone();
+    if let Some(column) = &sort {
+        let mut serializer = url::form_urlencoded::Serializer::new(page_query);
+        serializer
+            .append_pair("sort", column)
+            .append_pair("dir", if descending { "desc" } else { "asc" });
+        page_query = serializer.finish();
+    }
+
+    let filter_fields = table_info
+        .columns
+        .iter()
+        .map(|column| {
+            let current = filters.iter().find(|f| f.column == column.name);
+            FilterField {
+                column: column.name.clone(),
+                op: current
+                    .map(|f| f.operator.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                value: current.and_then(|f| f.value.clone()).unwrap_or_default(),
+                to: current.and_then(|f| f.to.clone()).unwrap_or_default(),
+            }
+        })
+        .collect();
 
     let table_data = TableData {
         name: table_name.clone(),
@@ -384,31 +833,80 @@ pub async fn table_content(
 
     Ok(TableContentTemplate {
         table_data: crate::models::TableDataForTemplate::from_table_data(table_data),
+        row_keys,
+        fk_links,
+        can_edit_rows: !key_columns.is_empty(),
         pagination,
+        sort_column: sort.unwrap_or_default(),
+        sort_desc: descending,
+        sort_links,
+        page_query,
+        filter_fields,
+        filter_operators: FilterOperator::ALL
+            .iter()
+            .map(|op| (op.as_str(), op.label()))
+            .collect(),
+        has_filters: !filters.is_empty(),
+        is_view: table_info.is_view,
         database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
         readonly: database_info.readonly,
-        flash_messages: vec![],
+        flash_messages,
         version: "0.1.0".to_string(),
     }
     .into_template_response())
 }
 
+/// Content page of the referenced table filtered to the row `row` points at.
+/// The link stays in the schema being browsed unless the referenced table
+/// lives in another one.
+fn foreign_key_link(
+    schema: &Schema,
+    fk: &ForeignKeyInfo,
+    columns: &[String],
+    row: &[CellValue],
+) -> Option<String> {
+    let mut serializer = url::form_urlencoded::Serializer::new(String::new());
+    for (column, referenced) in fk.columns.iter().zip(&fk.referenced_columns) {
+        let index = columns.iter().position(|c| c == column)?;
+        let value = row.get(index)?.to_form_value()?;
+        serializer
+            .append_pair(&format!("filter[{}].op", referenced), "eq")
+            .append_pair(&format!("filter[{}].value", referenced), &value);
+    }
+    let schema = match &fk.referenced_schema {
+        Some(referenced) => Schema(Some(referenced.clone())),
+        None => schema.clone(),
+    };
+    Some(format!(
+        "{}?{}",
+        schema.table_path(&fk.referenced_table, "content"),
+        serializer.finish()
+    ))
+}
+
 #[get("/table/<table_name>/query")]
 pub async fn table_query(
     table_name: String,
     db: &State<DatabasePool>,
     config: &State<DatabaseConfig>,
     _auth: AuthGuard,
+    schema: Schema,
 ) -> Result<TemplateResponse<TableQueryTemplate>, Status> {
     let pool = &db.0;
-    let manager = DatabaseManager::new(pool, config.inner().clone());
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
 
     let database_info = manager
         .get_database_info()
         .await
         .map_err(|_| Status::InternalServerError)?;
 
-    let default_sql = format!("SELECT * FROM \"{}\" LIMIT 100;", table_name);
+    let default_sql = format!(
+        "SELECT * FROM {} LIMIT 100;",
+        config
+            .database_type
+            .quote_qualified(&manager.qualify(&table_name))
+    );
 
     Ok(TableQueryTemplate::new(
         table_name.clone(),
@@ -418,8 +916,10 @@ pub async fn table_query(
         vec![],
         None,
         None,
+        None,
         String::new(),
         database_info.base_name(),
+        schema.prefix(),
         database_info.readonly,
         vec![],
         "0.1.0".to_string(),
@@ -434,9 +934,10 @@ pub async fn table_query_execute(
     db: &State<DatabasePool>,
     config: &State<DatabaseConfig>,
     _auth: AuthGuard,
+    schema: Schema,
 ) -> Result<TemplateResponse<TableQueryTemplate>, Status> {
     let pool = &db.0;
-    let manager = DatabaseManager::new(pool, config.inner().clone());
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
 
     let database_info = manager
         .get_database_info()
@@ -453,8 +954,10 @@ pub async fn table_query_execute(
             vec![],
             None,
             None,
+            None,
             "SQL query cannot be empty".to_string(),
             database_info.base_name(),
+            schema.prefix(),
             database_info.readonly,
             vec![],
             "0.1.0".to_string(),
@@ -462,7 +965,27 @@ pub async fn table_query_execute(
         .into_template_response());
     }
 
-    match manager.execute_query(sql).await {
+    if config.readonly && !statement::is_read_only_script(sql, &config.database_type) {
+        return Ok(TableQueryTemplate::new(
+            table_name.clone(),
+            sql.to_string(),
+            false,
+            vec![],
+            vec![],
+            None,
+            None,
+            None,
+            "Write operations are not allowed in read-only mode".to_string(),
+            database_info.base_name(),
+            schema.prefix(),
+            database_info.readonly,
+            vec![],
+            "0.1.0".to_string(),
+        )
+        .into_template_response());
+    }
+
+    match manager.run_statement(sql).await {
         Ok(query_result) => Ok(TableQueryTemplate::new(
             table_name.clone(),
             sql.to_string(),
@@ -470,9 +993,11 @@ pub async fn table_query_execute(
             query_result.columns,
             query_result.rows,
             query_result.rows_affected,
+            query_result.last_insert_id,
             None,
             String::new(),
             database_info.base_name(),
+            schema.prefix(),
             database_info.readonly,
             vec![],
             "0.1.0".to_string(),
@@ -486,8 +1011,10 @@ pub async fn table_query_execute(
             vec![],
             None,
             None,
+            None,
             format!("SQL Error: {}", e),
             database_info.base_name(),
+            schema.prefix(),
             database_info.readonly,
             vec![],
             "0.1.0".to_string(),
@@ -502,13 +1029,14 @@ pub async fn table_insert(
     db: &State<DatabasePool>,
     config: &State<DatabaseConfig>,
     _auth: AuthGuard,
+    schema: Schema,
 ) -> Result<TemplateResponse<TableInsertTemplate>, Status> {
     if config.readonly {
         return Err(Status::Forbidden);
     }
 
     let pool = &db.0;
-    let manager = DatabaseManager::new(pool, config.inner().clone());
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
 
     let database_info = manager
         .get_database_info()
@@ -516,14 +1044,28 @@ pub async fn table_insert(
         .map_err(|_| Status::InternalServerError)?;
 
     let table_info = manager
-        .get_table_info(&table_name)
+        .get_table_info(&manager.qualify(&table_name))
         .await
         .map_err(|_| Status::InternalServerError)?;
 
+    // Auto-increment columns number themselves unless told otherwise.
+    let fields = table_info
+        .columns
+        .into_iter()
+        .map(|column| {
+            let use_default = column.is_auto_increment;
+            RowField {
+                use_default,
+                ..RowField::new(column, Some(String::new()))
+            }
+        })
+        .collect();
+
     Ok(TableInsertTemplate {
         table_name: table_name.clone(),
-        columns: table_info.columns,
+        fields,
         database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
         readonly: database_info.readonly,
         flash_messages: vec![],
         version: "0.1.0".to_string(),
@@ -535,47 +1077,65 @@ pub async fn table_insert(
 #[post("/table/<table_name>/insert", data = "<form>")]
 pub async fn table_insert_execute(
     table_name: String,
-    form: Form<InsertForm>,
+    form: Form<InsertForm<'_>>,
     db: &State<DatabasePool>,
     config: &State<DatabaseConfig>,
     _auth: AuthGuard,
-) -> Result<Redirect, Status> {
+    schema: Schema,
+) -> Result<Result<Redirect, TemplateResponse<TableInsertTemplate>>, Status> {
     if config.readonly {
         return Err(Status::Forbidden);
     }
 
     let pool = &db.0;
-    let manager = DatabaseManager::new(pool, config.inner().clone());
-
-    // Build INSERT statement
-    let columns: Vec<String> = form.data.keys().cloned().collect();
-    let values: Vec<String> = form
-        .data
-        .values()
-        .map(|v| {
-            if v.is_empty() {
-                "NULL".to_string()
-            } else {
-                format!("'{}'", v.replace("'", "''"))
-            }
-        })
-        .collect();
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
 
-    let sql = format!(
-        "INSERT INTO \"{}\" ({}) VALUES ({})",
-        table_name,
-        columns
-            .iter()
-            .map(|c| format!("\"{}\"", c))
-            .collect::<Vec<_>>()
-            .join(", "),
-        values.join(", ")
-    );
+    let database_info = manager
+        .get_database_info()
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    let table_info = manager
+        .get_table_info(&manager.qualify(&table_name))
+        .await
+        .map_err(|_| Status::InternalServerError)?;
 
-    match manager.execute_query(&sql).await {
-        Ok(_) => Ok(Redirect::to(format!("/table/{}/content", table_name))),
-        Err(_) => Err(Status::InternalServerError),
+    let data = row_values(
+        &table_info.columns,
+        &form.data,
+        &form.null,
+        &form.default,
+        &form.file,
+    )
+    .await
+    .map_err(|_| Status::BadRequest)?;
+    let request = InsertRequest {
+        table: manager.qualify(&table_name),
+        data,
+    };
+
+    let builder = query_builder::insert(&config.database_type, &table_info, &request);
+
+    let error = match manager.execute(&builder).await {
+        Ok(_) => {
+            return Ok(Ok(Redirect::to(
+                schema.path(&format!("/table/{}/content", table_name)),
+            )));
+        }
+        Err(e) => format!("SQL Error: {}", e),
+    };
+
+    Ok(Err(TableInsertTemplate {
+        table_name,
+        fields: submitted_fields(table_info.columns, &form.data, &form.null, &form.default),
+        database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
+        readonly: database_info.readonly,
+        flash_messages: vec![],
+        version: "0.1.0".to_string(),
+        error,
     }
+    .into_template_response()))
 }
 
 #[get("/table/<table_name>/update?<pk..>")]
@@ -585,13 +1145,14 @@ pub async fn table_update(
     db: &State<DatabasePool>,
     config: &State<DatabaseConfig>,
     _auth: AuthGuard,
+    schema: Schema,
 ) -> Result<TemplateResponse<TableUpdateTemplate>, Status> {
     if config.readonly {
         return Err(Status::Forbidden);
     }
 
     let pool = &db.0;
-    let manager = DatabaseManager::new(pool, config.inner().clone());
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
 
     let database_info = manager
         .get_database_info()
@@ -599,92 +1160,282 @@ pub async fn table_update(
         .map_err(|_| Status::InternalServerError)?;
 
     let table_info = manager
-        .get_table_info(&table_name)
+        .get_table_info(&manager.qualify(&table_name))
         .await
         .map_err(|_| Status::InternalServerError)?;
 
-    // Get current row data
-    let mut where_conditions = vec![];
-    for (key, value) in &pk {
-        where_conditions.push(format!("\"{}\" = '{}'", key, value.replace("'", "''")));
-    }
-
-    let sql = format!(
-        "SELECT * FROM \"{}\" WHERE {}",
-        table_name,
-        where_conditions.join(" AND ")
-    );
+    let conditions = manager
+        .row_identity(&table_info)
+        .await
+        .map_err(|_| Status::InternalServerError)?
+        .conditions(&config.database_type, &pk)
+        .ok_or(Status::BadRequest)?;
+    let mut builder = query_builder::select_where(&config.database_type, &table_info, &conditions);
+    builder.push(" LIMIT 1");
 
     let query_result = manager
-        .execute_query(&sql)
+        .fetch(&builder)
         .await
-        .map_err(|_| Status::InternalServerError)?;
+        .map_err(|_| Status::InternalServerError)?
+        .with_column_types(&table_info);
 
+    let Some(row) = query_result.rows.first() else {
+        return Err(Status::NotFound);
+    };
     let mut row_data = HashMap::new();
-    if let Some(row) = query_result.rows.first() {
-        for (i, column) in query_result.columns.iter().enumerate() {
-            row_data.insert(column.clone(), row.get(i).unwrap_or(&None).clone());
-        }
+    for (column, value) in query_result.columns.iter().zip(row) {
+        // Blobs are replaced by upload, so only their size is shown.
+        let value = match value {
+            CellValue::Bytes(bytes) => Some(format!("{} bytes", bytes.len())),
+            other => other.to_form_value(),
+        };
+        row_data.insert(column.clone(), value);
     }
 
-    Ok(TableUpdateTemplate::new(
-        table_name.clone(),
-        table_info.columns,
-        row_data,
-        pk,
-        database_info.base_name(),
-        database_info.readonly,
-        vec![],
-        "0.1.0".to_string(),
-        String::new(),
-    )
+    let fields = table_info
+        .columns
+        .into_iter()
+        .map(|column| {
+            let value = row_data.get(&column.name).cloned().flatten();
+            let readonly = column.is_primary_key;
+            RowField {
+                readonly,
+                ..RowField::new(column, value)
+            }
+        })
+        .collect();
+
+    Ok(TableUpdateTemplate {
+        table_name,
+        fields,
+        key_values: sorted_pairs(&pk),
+        row_version: row_version(row),
+        database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
+        readonly: database_info.readonly,
+        flash_messages: vec![],
+        version: "0.1.0".to_string(),
+        error: String::new(),
+    }
     .into_template_response())
 }
 
 #[post("/table/<table_name>/update", data = "<form>")]
 pub async fn table_update_execute(
     table_name: String,
-    form: Form<UpdateForm>,
+    form: Form<UpdateForm<'_>>,
     db: &State<DatabasePool>,
     config: &State<DatabaseConfig>,
     _auth: AuthGuard,
-) -> Result<Redirect, Status> {
+    schema: Schema,
+) -> Result<UpdateResponse, Status> {
     if config.readonly {
         return Err(Status::Forbidden);
     }
 
     let pool = &db.0;
-    let manager = DatabaseManager::new(pool, config.inner().clone());
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
 
-    // Build UPDATE statement
-    let set_clauses: Vec<String> = form
-        .data
-        .iter()
-        .map(|(key, value)| {
-            if value.is_empty() {
-                format!("\"{}\" = NULL", key)
-            } else {
-                format!("\"{}\" = '{}'", key, value.replace("'", "''"))
+    let database_info = manager
+        .get_database_info()
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    let table_info = manager
+        .get_table_info(&manager.qualify(&table_name))
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    let conditions = manager
+        .row_identity(&table_info)
+        .await
+        .map_err(|_| Status::InternalServerError)?
+        .conditions(&config.database_type, &form.pk)
+        .ok_or(Status::BadRequest)?;
+
+    let data = row_values(
+        &table_info.columns,
+        &form.data,
+        &form.null,
+        &form.default,
+        &form.file,
+    )
+    .await
+    .map_err(|_| Status::BadRequest)?;
+    let request = UpdateRequest {
+        table: manager.qualify(&table_name),
+        data,
+        where_clause: conditions,
+    };
+
+    let current = match manager
+        .update_row(&table_info, &request, &form.row_version)
+        .await
+    {
+        Ok(RowUpdate::Updated) => {
+            return Ok(UpdateResponse::Saved(Redirect::to(
+                schema.path(&format!("/table/{}/content", table_name)),
+            )));
+        }
+        Ok(RowUpdate::Conflict(current)) => current,
+        Ok(RowUpdate::Missing) => vec![],
+        Err(e) => {
+            let mut fields =
+                submitted_fields(table_info.columns, &form.data, &form.null, &form.default);
+            for field in &mut fields {
+                field.readonly = field.column.is_primary_key;
             }
-        })
-        .collect();
+            return Ok(UpdateResponse::Failed(
+                TableUpdateTemplate {
+                    table_name,
+                    fields,
+                    key_values: sorted_pairs(&form.pk),
+                    row_version: form.row_version.clone(),
+                    database_name: database_info.base_name(),
+                    schema_prefix: schema.prefix(),
+                    readonly: database_info.readonly,
+                    flash_messages: vec![],
+                    version: "0.1.0".to_string(),
+                    error: format!("SQL Error: {}", e),
+                }
+                .into_template_response(),
+            ));
+        }
+    };
 
-    let mut where_conditions = vec![];
-    for (key, value) in &form.pk {
-        where_conditions.push(format!("\"{}\" = '{}'", key, value.replace("'", "''")));
+    let conflict = row_conflict(table_name, &table_info, current, &request, &form);
+    Ok(UpdateResponse::Conflict(
+        RowConflictTemplate {
+            database_name: database_info.base_name(),
+            schema_prefix: schema.prefix(),
+            readonly: database_info.readonly,
+            ..conflict
+        }
+        .into_template_response(),
+    ))
+}
+
+/// Conflict page comparing the row's `current` values, empty when it was
+/// deleted, with what the edit form submitted.
+fn row_conflict(
+    table_name: String,
+    table_info: &TableInfo,
+    current: Vec<(String, CellValue)>,
+    request: &UpdateRequest,
+    form: &UpdateForm<'_>,
+) -> RowConflictTemplate {
+    let columns: Vec<ConflictColumn> = if current.is_empty() {
+        vec![]
+    } else {
+        table_info
+            .columns
+            .iter()
+            .map(|column| {
+                let current = current
+                    .iter()
+                    .find(|(name, _)| name == &column.name)
+                    .map(|(_, value)| value.to_string())
+                    .unwrap_or_default();
+                let submitted = match request.data.get(&column.name) {
+                    Some(ColumnValue::Value(value)) => value.to_string(),
+                    Some(ColumnValue::Default) => "DEFAULT".to_string(),
+                    None => current.clone(),
+                };
+                ConflictColumn {
+                    name: column.name.clone(),
+                    differs: current != submitted,
+                    current,
+                    submitted,
+                }
+            })
+            .collect()
+    };
+    let row_version = row_version(
+        &current
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect::<Vec<_>>(),
+    );
+
+    let mut resubmit = Vec::new();
+    for (column, value) in sorted_pairs(&form.data) {
+        resubmit.push((format!("data[{}]", column), value));
+    }
+    for (field, flags) in [("null", &form.null), ("default", &form.default)] {
+        for (column, set) in flags {
+            if *set {
+                resubmit.push((format!("{}[{}]", field, column), "true".to_string()));
+            }
+        }
     }
 
-    let sql = format!(
-        "UPDATE \"{}\" SET {} WHERE {}",
+    RowConflictTemplate {
         table_name,
-        set_clauses.join(", "),
-        where_conditions.join(" AND ")
-    );
+        columns,
+        key_values: sorted_pairs(&form.pk),
+        resubmit,
+        row_version,
+        has_upload: form.file.values().any(|f| f.len() > 0),
+        database_name: String::new(),
+        schema_prefix: String::new(),
+        readonly: false,
+        flash_messages: vec![],
+        version: "0.1.0".to_string(),
+    }
+}
+
+#[post("/table/<table_name>/delete", data = "<form>")]
+pub async fn table_delete_execute(
+    table_name: String,
+    form: Form<DeleteForm>,
+    db: &State<DatabasePool>,
+    config: &State<DatabaseConfig>,
+    _auth: AuthGuard,
+    schema: Schema,
+) -> Result<Redirect, Status> {
+    if config.readonly {
+        return Err(Status::Forbidden);
+    }
+
+    let pool = &db.0;
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
 
-    match manager.execute_query(&sql).await {
-        Ok(_) => Ok(Redirect::to(format!("/table/{}/content", table_name))),
-        Err(_) => Err(Status::InternalServerError),
+    let table_info = manager
+        .get_table_info(&manager.qualify(&table_name))
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    // Refuse rather than guess when rows can't be addressed individually.
+    let identity = manager
+        .row_identity(&table_info)
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    if identity.key_columns(&config.database_type).is_empty() {
+        return Err(Status::UnprocessableEntity);
     }
+
+    let mut requests = Vec::new();
+    for key in &form.keys {
+        let key: HashMap<String, String> = url::form_urlencoded::parse(key.as_bytes())
+            .into_owned()
+            .collect();
+        let where_clause = identity
+            .conditions(&config.database_type, &key)
+            .ok_or(Status::BadRequest)?;
+
+        requests.push(DeleteRequest {
+            table: manager.qualify(&table_name),
+            where_clause,
+        });
+    }
+
+    manager
+        .delete_rows(&table_info, &requests)
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    Ok(Redirect::to(
+        schema.path(&format!("/table/{}/content", table_name)),
+    ))
 }
 
 #[get("/table/<table_name>/export")]
@@ -693,9 +1444,10 @@ pub async fn table_export(
     db: &State<DatabasePool>,
     config: &State<DatabaseConfig>,
     _auth: AuthGuard,
+    schema: Schema,
 ) -> Result<TemplateResponse<TableExportTemplate>, Status> {
     let pool = &db.0;
-    let manager = DatabaseManager::new(pool, config.inner().clone());
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
 
     let database_info = manager
         .get_database_info()
@@ -703,7 +1455,7 @@ pub async fn table_export(
         .map_err(|_| Status::InternalServerError)?;
 
     let table_info = manager
-        .get_table_info(&table_name)
+        .get_table_info(&manager.qualify(&table_name))
         .await
         .map_err(|_| Status::InternalServerError)?;
 
@@ -713,26 +1465,132 @@ pub async fn table_export(
         table_name: table_name.clone(),
         columns,
         database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
         readonly: database_info.readonly,
         flash_messages: vec![],
         version: "0.1.0".to_string(),
+        error: String::new(),
     }
     .into_template_response())
 }
 
+#[post("/table/<table_name>/export", data = "<form>")]
+pub async fn table_export_execute(
+    table_name: String,
+    form: Form<ExportForm>,
+    db: &State<DatabasePool>,
+    config: &State<DatabaseConfig>,
+    _auth: AuthGuard,
+    schema: Schema,
+) -> Result<Result<ExportDownload, TemplateResponse<TableExportTemplate>>, Status> {
+    let pool = &db.0;
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
+
+    let database_info = manager
+        .get_database_info()
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    let table_info = manager
+        .get_table_info(&manager.qualify(&table_name))
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    let table_columns: Vec<String> = table_info.columns.iter().map(|c| c.name.clone()).collect();
+
+    let request = ExportRequest {
+        table: manager.qualify(&table_name),
+        format: form.format.clone(),
+        columns: Some(form.columns.clone()).filter(|c| !c.is_empty()),
+        where_clause: form.where_clause.clone(),
+        limit: form.limit,
+    };
+
+    let render_error = |error: String| {
+        TableExportTemplate {
+            table_name: table_name.clone(),
+            columns: table_columns.clone(),
+            database_name: database_info.base_name(),
+            schema_prefix: schema.prefix(),
+            readonly: database_info.readonly,
+            flash_messages: vec![],
+            version: "0.1.0".to_string(),
+            error,
+        }
+        .into_template_response()
+    };
+
+    let format = match ExportFormat::parse(&request.format) {
+        Some(format) => format,
+        None => {
+            return Ok(Err(render_error(format!(
+                "Unsupported export format: {}",
+                request.format
+            ))))
+        }
+    };
+
+    // Only export columns that actually exist, in table order.
+    let columns: Vec<ColumnInfo> = match request.columns {
+        Some(ref selected) => {
+            if let Some(unknown) = selected.iter().find(|c| !table_columns.contains(c)) {
+                return Ok(Err(render_error(format!("Unknown column: {}", unknown))));
+            }
+            table_info
+                .columns
+                .iter()
+                .filter(|c| selected.contains(&c.name))
+                .cloned()
+                .collect()
+        }
+        None => table_info.columns.clone(),
+    };
+
+    // The WHERE clause is raw SQL, spliced into the export query as written,
+    // so it must not add statements of its own. Semicolons inside literals
+    // and comments are fine.
+    let sql = build_export_sql(&request, &columns, &config.database_type);
+    if statement::split(&sql, &config.database_type).len() != 1 {
+        return Ok(Err(render_error(
+            "WHERE clause must not contain multiple statements".to_string(),
+        )));
+    }
+
+    // Validate the statement up front, since errors can no longer be
+    // reported once the download has started streaming.
+    let probe = ExportRequest {
+        limit: Some(0),
+        ..request.clone()
+    };
+    let probe_sql = build_export_sql(&probe, &columns, &config.database_type);
+    if let Err(e) = manager.execute_query(&probe_sql).await {
+        return Ok(Err(render_error(format!("SQL Error: {}", e))));
+    }
+
+    Ok(Ok(export_download(
+        pool.clone(),
+        sql,
+        request.table,
+        columns,
+        format,
+        config.database_type.clone(),
+    )))
+}
+
 #[get("/table/<table_name>/import")]
 pub async fn table_import(
     table_name: String,
     db: &State<DatabasePool>,
     config: &State<DatabaseConfig>,
     _auth: AuthGuard,
+    schema: Schema,
 ) -> Result<TemplateResponse<TableImportTemplate>, Status> {
     if config.readonly {
         return Err(Status::Forbidden);
     }
 
     let pool = &db.0;
-    let manager = DatabaseManager::new(pool, config.inner().clone());
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
 
     let database_info = manager
         .get_database_info()
@@ -740,7 +1598,7 @@ pub async fn table_import(
         .map_err(|_| Status::InternalServerError)?;
 
     let table_info = manager
-        .get_table_info(&table_name)
+        .get_table_info(&manager.qualify(&table_name))
         .await
         .map_err(|_| Status::InternalServerError)?;
 
@@ -750,10 +1608,239 @@ pub async fn table_import(
         table_name: table_name.clone(),
         columns,
         database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
         readonly: database_info.readonly,
         flash_messages: vec![],
         version: "0.1.0".to_string(),
         error: String::new(),
+        row_errors: vec![],
     }
     .into_template_response())
 }
+
+#[post("/table/<table_name>/import", data = "<form>")]
+pub async fn table_import_execute(
+    table_name: String,
+    form: Form<ImportForm<'_>>,
+    db: &State<DatabasePool>,
+    config: &State<DatabaseConfig>,
+    _auth: AuthGuard,
+    schema: Schema,
+) -> Result<TemplateResponse<TableImportTemplate>, Status> {
+    if config.readonly {
+        return Err(Status::Forbidden);
+    }
+
+    let pool = &db.0;
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
+
+    let database_info = manager
+        .get_database_info()
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    let mut table_info = manager
+        .get_table_info(&manager.qualify(&table_name))
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    let render = |columns: &[ColumnInfo],
+                  flash_messages: Vec<Flash>,
+                  error: String,
+                  row_errors: Vec<ImportRowError>| {
+        TableImportTemplate {
+            table_name: table_name.clone(),
+            columns: columns.iter().map(|c| c.name.clone()).collect(),
+            database_name: database_info.base_name(),
+            schema_prefix: schema.prefix(),
+            readonly: database_info.readonly,
+            flash_messages,
+            version: "0.1.0".to_string(),
+            error,
+            row_errors,
+        }
+        .into_template_response()
+    };
+
+    // An uploaded file takes precedence over pasted data.
+    let data = match form.import_file {
+        Some(ref file) if file.len() > 0 => {
+            let mut data = String::new();
+            let read = match file.open().await {
+                Ok(mut reader) => reader.read_to_string(&mut data).await.map(|_| ()),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = read {
+                return Ok(render(
+                    &table_info.columns,
+                    vec![],
+                    format!("Failed to read uploaded file: {}", e),
+                    vec![],
+                ));
+            }
+            data
+        }
+        _ => form.import_data.clone().unwrap_or_default(),
+    };
+
+    let request = ImportRequest {
+        table: manager.qualify(&table_name),
+        format: form.format.clone(),
+        data,
+        create_columns: form.create_columns.unwrap_or(false),
+        ignore_errors: form.ignore_errors.unwrap_or(false),
+    };
+
+    let import_data = match parse_import(&request) {
+        Ok(import_data) => import_data,
+        Err(e) => return Ok(render(&table_info.columns, vec![], e, vec![])),
+    };
+
+    let mut flash_messages = vec![];
+
+    let unknown_fields: Vec<(usize, String)> = import_data
+        .fields
+        .iter()
+        .enumerate()
+        .filter(|(_, field)| match_column(field, &table_info.columns).is_none())
+        .map(|(i, field)| (i, field.clone()))
+        .collect();
+
+    // New columns are added in the import's transaction, so an aborted
+    // import doesn't leave them behind. MySQL commits ALTER TABLE at once,
+    // so there they are added up front and kept either way.
+    let transactional_ddl = !matches!(config.database_type, DatabaseType::Mysql);
+    let mut schema_changes = vec![];
+    let mut new_columns = vec![];
+    if !unknown_fields.is_empty() {
+        if request.create_columns {
+            for (i, field) in &unknown_fields {
+                let column = AddColumnRequest {
+                    table: manager.qualify(&table_name),
+                    column: CreateColumnRequest {
+                        name: field.clone(),
+                        data_type: infer_column_type(&import_data, *i, &config.database_type),
+                        nullable: true,
+                        default_value: None,
+                        primary_key: false,
+                        auto_increment: false,
+                        unique: false,
+                    },
+                };
+                if transactional_ddl {
+                    schema_changes.push(manager.add_column_sql(&column));
+                } else if let Err(e) = manager.add_column(&column).await {
+                    return Ok(render(
+                        &table_info.columns,
+                        vec![],
+                        format!("Failed to create column {}: {}", field, e),
+                        vec![],
+                    ));
+                }
+                new_columns.push(ColumnInfo::new(field, &column.column.data_type));
+            }
+        } else {
+            flash_messages.push(Flash {
+                category: "warning".to_string(),
+                message: format!(
+                    "Ignored fields without a matching column: {}",
+                    unknown_fields
+                        .iter()
+                        .map(|(_, f)| f.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            });
+        }
+    }
+    let created = new_columns
+        .iter()
+        .map(|c| c.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let target_columns: Vec<Option<ColumnInfo>> = import_data
+        .fields
+        .iter()
+        .map(|field| {
+            match_column(field, &table_info.columns)
+                .or_else(|| match_column(field, &new_columns))
+                .cloned()
+        })
+        .collect();
+
+    let total = import_data.rows.len();
+    let outcome = import_rows(
+        pool,
+        &config.database_type,
+        &table_info.qualified_name(),
+        &target_columns,
+        import_data,
+        &schema_changes,
+        request.ignore_errors,
+    )
+    .await;
+
+    // Show the columns the table has now.
+    if !new_columns.is_empty() {
+        table_info = manager
+            .get_table_info(&manager.qualify(&table_name))
+            .await
+            .map_err(|_| Status::InternalServerError)?;
+    }
+    let kept_columns = !new_columns.is_empty()
+        && (!transactional_ddl || outcome.as_ref().is_ok_and(|o| o.committed));
+    if kept_columns {
+        flash_messages.push(Flash {
+            category: "info".to_string(),
+            message: format!("Created columns: {}", created),
+        });
+    }
+
+    let outcome = match outcome {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            return Ok(render(
+                &table_info.columns,
+                flash_messages,
+                format!("SQL Error: {}", e),
+                vec![],
+            ))
+        }
+    };
+
+    let error = if outcome.committed {
+        flash_messages.push(Flash {
+            category: if outcome.errors.is_empty() {
+                "success".to_string()
+            } else {
+                "warning".to_string()
+            },
+            message: format!(
+                "Imported {} of {} rows ({} failed)",
+                outcome.imported,
+                total,
+                outcome.errors.len()
+            ),
+        });
+        String::new()
+    } else if kept_columns {
+        format!(
+            "Import aborted and rolled back; no rows were imported, but the \
+             created columns {} remain as MySQL can't roll back ALTER TABLE. \
+             Enable \"Ignore Errors\" to skip failing rows.",
+            created
+        )
+    } else {
+        "Import aborted and rolled back; no rows were imported. \
+         Enable \"Ignore Errors\" to skip failing rows."
+            .to_string()
+    };
+
+    Ok(render(
+        &table_info.columns,
+        flash_messages,
+        error,
+        outcome.errors,
+    ))
+}
@@ -0,0 +1,218 @@
+use crate::config::{DatabaseConfig, DatabaseManager, DatabasePool, DatabaseType, TriggerInfo};
+use crate::models::{FlashMessage as Flash, QualifiedName, TriggerDetail};
+use crate::routes::index::AuthGuard;
+use crate::routes::schema::Schema;
+use crate::statement;
+use crate::template::{IntoTemplateResponse, TemplateResponse};
+use askama::Template;
+use rocket::form::Form;
+use rocket::http::Status;
+use rocket::response::Redirect;
+use rocket::{get, post, State};
+
+#[derive(Template)]
+#[template(path = "trigger.html")]
+pub struct TriggerTemplate {
+    pub table_name: String,
+    pub trigger: TriggerDetail,
+    pub database_name: String,
+    pub schema_prefix: String,
+    pub readonly: bool,
+    pub flash_messages: Vec<Flash>,
+    pub version: String,
+}
+
+#[derive(Template)]
+#[template(path = "create_trigger.html")]
+pub struct CreateTriggerTemplate {
+    pub table_name: String,
+    pub sql: String,
+    pub database_name: String,
+    pub schema_prefix: String,
+    pub readonly: bool,
+    pub flash_messages: Vec<Flash>,
+    pub version: String,
+    pub error: String,
+}
+
+#[derive(rocket::FromForm)]
+pub struct CreateTriggerForm {
+    pub sql: String,
+}
+
+impl From<TriggerInfo> for TriggerDetail {
+    fn from(trigger: TriggerInfo) -> Self {
+        TriggerDetail {
+            name: trigger.name,
+            event: trigger.event,
+            timing: trigger.timing,
+            table_name: trigger.table,
+            definition: trigger.definition,
+        }
+    }
+}
+
+/// Starting point for the trigger editor.
+fn trigger_template(database_type: &DatabaseType, table: &QualifiedName) -> String {
+    let table = database_type.quote_qualified(table);
+    match database_type {
+        DatabaseType::Sqlite => format!(
+            "CREATE TRIGGER trigger_name\nAFTER INSERT ON {}\nFOR EACH ROW\nBEGIN\n    -- statements\nEND;",
+            table
+        ),
+        DatabaseType::Mysql => format!(
+            "CREATE TRIGGER trigger_name\nBEFORE INSERT ON {}\nFOR EACH ROW\nBEGIN\n    -- statements\nEND;",
+            table
+        ),
+        // Postgres triggers call a function, which may be created alongside.
+        DatabaseType::Postgres => format!(
+            "CREATE OR REPLACE FUNCTION trigger_function() RETURNS trigger AS $$\nBEGIN\n    RETURN NEW;\nEND;\n$$ LANGUAGE plpgsql;\n\nCREATE TRIGGER trigger_name\nBEFORE INSERT ON {}\nFOR EACH ROW EXECUTE FUNCTION trigger_function();",
+            table
+        ),
+    }
+}
+
+#[get("/table/<table_name>/trigger/<trigger_name>")]
+pub async fn trigger(
+    table_name: String,
+    trigger_name: String,
+    db: &State<DatabasePool>,
+    config: &State<DatabaseConfig>,
+    _auth: AuthGuard,
+    schema: Schema,
+) -> Result<TemplateResponse<TriggerTemplate>, Status> {
+    let pool = &db.0;
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
+
+    let database_info = manager
+        .get_database_info()
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    let trigger = manager
+        .get_triggers(Some(&manager.qualify(&table_name)))
+        .await
+        .map_err(|_| Status::InternalServerError)?
+        .into_iter()
+        .find(|t| t.name == trigger_name)
+        .ok_or(Status::NotFound)?;
+
+    Ok(TriggerTemplate {
+        table_name,
+        trigger: trigger.into(),
+        database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
+        readonly: database_info.readonly,
+        flash_messages: vec![],
+        version: "0.1.0".to_string(),
+    }
+    .into_template_response())
+}
+
+#[get("/table/<table_name>/create-trigger")]
+pub async fn create_trigger(
+    table_name: String,
+    db: &State<DatabasePool>,
+    config: &State<DatabaseConfig>,
+    _auth: AuthGuard,
+    schema: Schema,
+) -> Result<TemplateResponse<CreateTriggerTemplate>, Status> {
+    if config.readonly {
+        return Err(Status::Forbidden);
+    }
+
+    let pool = &db.0;
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
+
+    let database_info = manager
+        .get_database_info()
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    Ok(CreateTriggerTemplate {
+        sql: trigger_template(&config.database_type, &manager.qualify(&table_name)),
+        table_name,
+        database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
+        readonly: database_info.readonly,
+        flash_messages: vec![],
+        version: "0.1.0".to_string(),
+        error: String::new(),
+    }
+    .into_template_response())
+}
+
+#[post("/table/<table_name>/create-trigger", data = "<form>")]
+pub async fn create_trigger_execute(
+    table_name: String,
+    form: Form<CreateTriggerForm>,
+    db: &State<DatabasePool>,
+    config: &State<DatabaseConfig>,
+    _auth: AuthGuard,
+    schema: Schema,
+) -> Result<Result<Redirect, TemplateResponse<CreateTriggerTemplate>>, Status> {
+    if config.readonly {
+        return Err(Status::Forbidden);
+    }
+
+    let pool = &db.0;
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
+
+    let database_info = manager
+        .get_database_info()
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    let statements = statement::split(&form.sql, &config.database_type);
+    let error = if !statements.iter().any(|s| statement::is_create_trigger(s)) {
+        "The script must contain a CREATE TRIGGER statement".to_string()
+    } else {
+        match manager.create_trigger(&statements).await {
+            Ok(()) => {
+                return Ok(Ok(Redirect::to(
+                    schema.path(&format!("/table/{}/structure", table_name)),
+                )))
+            }
+            Err(e) => format!("SQL Error: {}", e),
+        }
+    };
+
+    Ok(Err(CreateTriggerTemplate {
+        table_name,
+        sql: form.sql.clone(),
+        database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
+        readonly: database_info.readonly,
+        flash_messages: vec![],
+        version: "0.1.0".to_string(),
+        error,
+    }
+    .into_template_response()))
+}
+
+#[post("/table/<table_name>/trigger/<trigger_name>/drop")]
+pub async fn drop_trigger_execute(
+    table_name: String,
+    trigger_name: String,
+    db: &State<DatabasePool>,
+    config: &State<DatabaseConfig>,
+    _auth: AuthGuard,
+    schema: Schema,
+) -> Result<Redirect, Status> {
+    if config.readonly {
+        return Err(Status::Forbidden);
+    }
+
+    let pool = &db.0;
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
+
+    match manager
+        .drop_trigger(&manager.qualify(&table_name), &trigger_name)
+        .await
+    {
+        Ok(_) => Ok(Redirect::to(
+            schema.path(&format!("/table/{}/structure", table_name)),
+        )),
+        Err(_) => Err(Status::InternalServerError),
+    }
+}
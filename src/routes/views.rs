@@ -0,0 +1,269 @@
+use crate::config::{DatabaseConfig, DatabaseManager, DatabasePool, DatabaseType};
+use crate::models::{CreateViewRequest, FlashMessage as Flash, ViewInfo};
+use crate::routes::index::AuthGuard;
+use crate::routes::schema::Schema;
+use crate::template::{IntoTemplateResponse, TemplateResponse};
+use askama::Template;
+use rocket::form::Form;
+use rocket::http::Status;
+use rocket::response::Redirect;
+use rocket::{get, post, State};
+
+#[derive(Template)]
+#[template(path = "view.html")]
+pub struct ViewTemplate {
+    pub view: ViewInfo,
+    pub database_name: String,
+    pub schema_prefix: String,
+    pub readonly: bool,
+    pub flash_messages: Vec<Flash>,
+    pub version: String,
+}
+
+#[derive(Template)]
+#[template(path = "create_view.html")]
+pub struct CreateViewTemplate {
+    pub name: String,
+    pub query: String,
+    pub materialized: bool,
+    // Editing an existing view, which is replaced on save.
+    pub replace: bool,
+    pub supports_materialized: bool,
+    pub database_name: String,
+    pub schema_prefix: String,
+    pub readonly: bool,
+    pub flash_messages: Vec<Flash>,
+    pub version: String,
+    pub error: String,
+}
+
+#[derive(rocket::FromForm)]
+pub struct CreateViewForm {
+    pub name: String,
+    pub query: String,
+    pub materialized: bool,
+    pub replace: bool,
+}
+
+async fn find_view(manager: &DatabaseManager<'_>, name: &str) -> Result<ViewInfo, Status> {
+    manager
+        .get_views()
+        .await
+        .map_err(|_| Status::InternalServerError)?
+        .into_iter()
+        .find(|v| v.name == name)
+        .ok_or(Status::NotFound)
+}
+
+#[get("/view/<view_name>")]
+pub async fn view(
+    view_name: String,
+    db: &State<DatabasePool>,
+    config: &State<DatabaseConfig>,
+    _auth: AuthGuard,
+    schema: Schema,
+) -> Result<TemplateResponse<ViewTemplate>, Status> {
+    let pool = &db.0;
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
+
+    let database_info = manager
+        .get_database_info()
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    let view = find_view(&manager, &view_name).await?;
+
+    Ok(ViewTemplate {
+        view,
+        database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
+        readonly: database_info.readonly,
+        flash_messages: vec![],
+        version: "0.1.0".to_string(),
+    }
+    .into_template_response())
+}
+
+#[get("/create-view")]
+pub async fn create_view(
+    db: &State<DatabasePool>,
+    config: &State<DatabaseConfig>,
+    _auth: AuthGuard,
+    schema: Schema,
+) -> Result<TemplateResponse<CreateViewTemplate>, Status> {
+    if config.readonly {
+        return Err(Status::Forbidden);
+    }
+
+    let pool = &db.0;
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
+
+    let database_info = manager
+        .get_database_info()
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    Ok(CreateViewTemplate {
+        name: String::new(),
+        query: String::new(),
+        materialized: false,
+        replace: false,
+        supports_materialized: matches!(config.database_type, DatabaseType::Postgres),
+        database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
+        readonly: database_info.readonly,
+        flash_messages: vec![],
+        version: "0.1.0".to_string(),
+        error: String::new(),
+    }
+    .into_template_response())
+}
+
+#[get("/view/<view_name>/edit")]
+pub async fn edit_view(
+    view_name: String,
+    db: &State<DatabasePool>,
+    config: &State<DatabaseConfig>,
+    _auth: AuthGuard,
+    schema: Schema,
+) -> Result<TemplateResponse<CreateViewTemplate>, Status> {
+    if config.readonly {
+        return Err(Status::Forbidden);
+    }
+
+    let pool = &db.0;
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
+
+    let database_info = manager
+        .get_database_info()
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    let view = find_view(&manager, &view_name).await?;
+
+    Ok(CreateViewTemplate {
+        name: view.name,
+        query: view.definition,
+        materialized: view.materialized,
+        replace: true,
+        supports_materialized: matches!(config.database_type, DatabaseType::Postgres),
+        database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
+        readonly: database_info.readonly,
+        flash_messages: vec![],
+        version: "0.1.0".to_string(),
+        error: String::new(),
+    }
+    .into_template_response())
+}
+
+#[post("/create-view", data = "<form>")]
+pub async fn create_view_execute(
+    form: Form<CreateViewForm>,
+    db: &State<DatabasePool>,
+    config: &State<DatabaseConfig>,
+    _auth: AuthGuard,
+    schema: Schema,
+) -> Result<Result<Redirect, TemplateResponse<CreateViewTemplate>>, Status> {
+    if config.readonly {
+        return Err(Status::Forbidden);
+    }
+
+    let pool = &db.0;
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
+
+    let database_info = manager
+        .get_database_info()
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    let request = CreateViewRequest {
+        name: manager.qualify(form.name.trim()),
+        query: form.query.clone(),
+        materialized: form.materialized,
+        replace: form.replace,
+    };
+
+    let error = if request.name.name.is_empty() || request.query.trim().is_empty() {
+        "A view needs a name and a query".to_string()
+    } else {
+        match manager.create_view(&request).await {
+            Ok(()) => {
+                return Ok(Ok(Redirect::to(
+                    schema.path(&format!("/view/{}", request.name.name)),
+                )))
+            }
+            Err(e) => format!("SQL Error: {}", e),
+        }
+    };
+
+    Ok(Err(CreateViewTemplate {
+        name: request.name.name,
+        query: request.query,
+        materialized: request.materialized,
+        replace: request.replace,
+        supports_materialized: matches!(config.database_type, DatabaseType::Postgres),
+        database_name: database_info.base_name(),
+        schema_prefix: schema.prefix(),
+        readonly: database_info.readonly,
+        flash_messages: vec![],
+        version: "0.1.0".to_string(),
+        error,
+    }
+    .into_template_response()))
+}
+
+#[post("/view/<view_name>/drop")]
+pub async fn drop_view_execute(
+    view_name: String,
+    db: &State<DatabasePool>,
+    config: &State<DatabaseConfig>,
+    _auth: AuthGuard,
+    schema: Schema,
+) -> Result<Redirect, Status> {
+    if config.readonly {
+        return Err(Status::Forbidden);
+    }
+
+    let pool = &db.0;
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
+
+    let view = find_view(&manager, &view_name).await?;
+
+    match manager
+        .drop_view(&manager.qualify(&view.name), view.materialized)
+        .await
+    {
+        Ok(_) => Ok(Redirect::to(schema.path("/tables"))),
+        Err(_) => Err(Status::InternalServerError),
+    }
+}
+
+#[post("/view/<view_name>/refresh")]
+pub async fn refresh_view_execute(
+    view_name: String,
+    db: &State<DatabasePool>,
+    config: &State<DatabaseConfig>,
+    _auth: AuthGuard,
+    schema: Schema,
+) -> Result<Redirect, Status> {
+    if config.readonly {
+        return Err(Status::Forbidden);
+    }
+
+    let pool = &db.0;
+    let manager = DatabaseManager::new(pool, config.inner().clone()).with_schema(schema.0.clone());
+
+    let view = find_view(&manager, &view_name).await?;
+    if !view.materialized {
+        return Err(Status::BadRequest);
+    }
+
+    match manager
+        .refresh_materialized_view(&manager.qualify(&view.name))
+        .await
+    {
+        Ok(_) => Ok(Redirect::to(schema.path(&format!("/view/{}", view.name)))),
+        Err(_) => Err(Status::InternalServerError),
+    }
+}
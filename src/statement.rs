@@ -0,0 +1,1231 @@
+use crate::config::DatabaseType;
+
+/// Strip leading whitespace, comments and opening parentheses so the first
+/// keyword of a statement can be inspected.
+fn skip_preamble(sql: &str) -> &str {
+    let mut rest = sql;
+    loop {
+        let trimmed = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '(');
+        if let Some(comment) = trimmed.strip_prefix("--") {
+            rest = comment.split_once('\n').map(|(_, tail)| tail).unwrap_or("");
+        } else if let Some(comment) = trimmed.strip_prefix("/*") {
+            rest = comment.split_once("*/").map(|(_, tail)| tail).unwrap_or("");
+        } else {
+            return trimmed;
+        }
+    }
+}
+
+/// First keyword of the statement, upper-cased.
+pub fn leading_keyword(sql: &str) -> String {
+    skip_preamble(sql)
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+/// How a statement has to be run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatementKind {
+    /// SELECT, WITH or VALUES: returns rows and can be wrapped in a subquery,
+    /// e.g. to paginate or count it.
+    Query,
+    /// Returns rows but can't be wrapped, like SHOW, PRAGMA or DML with a
+    /// RETURNING clause.
+    Rows,
+    /// Doesn't return rows; only the number of affected rows is reported.
+    Execute,
+}
+
+pub fn classify(sql: &str) -> StatementKind {
+    match leading_keyword(sql).as_str() {
+        "SELECT" | "WITH" | "VALUES" => StatementKind::Query,
+        "SHOW" | "DESCRIBE" | "DESC" | "EXPLAIN" | "PRAGMA" | "TABLE" => StatementKind::Rows,
+        "INSERT" | "UPDATE" | "DELETE" | "REPLACE" if has_keyword(sql, "RETURNING") => {
+            StatementKind::Rows
+        }
+        _ => StatementKind::Execute,
+    }
+}
+
+/// Whether the statement is a plain query that can be wrapped in a
+/// subquery, e.g. to paginate or count it.
+pub fn is_query(sql: &str) -> bool {
+    classify(sql) == StatementKind::Query
+}
+
+/// Whether the statement produces a result set.
+pub fn returns_rows(sql: &str) -> bool {
+    classify(sql) != StatementKind::Execute
+}
+
+/// Whether `keyword` appears as a word outside quoted strings and
+/// identifiers.
+fn has_keyword(sql: &str, keyword: &str) -> bool {
+    let mut chars = sql.chars();
+    let mut word = String::new();
+    while let Some(c) = chars.next() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            word.push(c);
+            continue;
+        }
+        if word.eq_ignore_ascii_case(keyword) {
+            return true;
+        }
+        word.clear();
+        if matches!(c, '\'' | '"' | '`') {
+            // Doubled quotes inside the literal simply reopen it.
+            for inner in chars.by_ref() {
+                if inner == c {
+                    break;
+                }
+            }
+        }
+    }
+    word.eq_ignore_ascii_case(keyword)
+}
+
+/// The statement without trailing semicolons and whitespace, ready to be
+/// embedded in a subquery.
+pub fn strip_terminator(sql: &str) -> &str {
+    sql.trim_end_matches(|c: char| c.is_whitespace() || c == ';')
+}
+
+/// An expression without enclosing parentheses and trailing Postgres casts,
+/// so `('x'::text)` and `'x'` compare equal. Only casts outside parentheses
+/// and quotes are removed.
+pub fn without_casts(sql: &str) -> &str {
+    let mut sql = sql.trim();
+    loop {
+        let bytes = sql.as_bytes();
+        let mut depth = 0;
+        let mut cast = None;
+        // Closing parenthesis matching one at the start, if any.
+        let mut outer_close = None;
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\'' | b'"' => {
+                    i = skip_quoted(bytes, i, false);
+                    continue;
+                }
+                b'(' => depth += 1,
+                b')' => {
+                    depth -= 1;
+                    if depth == 0 && bytes[0] == b'(' && outer_close.is_none() {
+                        outer_close = Some(i);
+                    }
+                }
+                b':' if depth == 0 && bytes.get(i + 1) == Some(&b':') => {
+                    cast = Some(i);
+                    i += 2;
+                    continue;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+
+        let type_name = cast.map(|at| &sql[at + 2..]).filter(|name| {
+            !name.trim().is_empty()
+                && name
+                    .bytes()
+                    .all(|c| is_word_byte(c) || matches!(c, b' ' | b'"' | b'.' | b'[' | b']'))
+        });
+        if let (Some(at), Some(_)) = (cast, type_name) {
+            sql = sql[..at].trim_end();
+        } else if outer_close.is_some_and(|close| close + 1 == bytes.len()) {
+            sql = sql[1..bytes.len() - 1].trim();
+        } else {
+            return sql;
+        }
+    }
+}
+
+/// Split a script into its statements, without terminators. Semicolons in
+/// string literals, quoted identifiers, comments, dollar-quoted bodies and
+/// the BEGIN ... END body of a trigger or routine don't end a statement.
+pub fn split(sql: &str, database_type: &DatabaseType) -> Vec<String> {
+    let mysql = matches!(database_type, DatabaseType::Mysql);
+    let bytes = sql.as_bytes();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    // Per-statement state for BEGIN ... END bodies.
+    let mut first_word: Option<String> = None;
+    let mut compound = false;
+    let mut depth = 0usize;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        match c {
+            b'\'' | b'"' | b'`' => {
+                // MySQL strings and Postgres E'...' strings use backslash escapes.
+                let escaped_string = c == b'\''
+                    && i > 0
+                    && matches!(bytes[i - 1], b'E' | b'e')
+                    && (i == 1 || !is_word_byte(bytes[i - 2]));
+                let backslash = (mysql && c != b'`') || escaped_string;
+                i = skip_quoted(bytes, i, backslash);
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => i = skip_line(bytes, i),
+            b'#' if mysql => i = skip_line(bytes, i),
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = sql[i + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| i + 2 + end + 2);
+            }
+            b'$' if !mysql => match dollar_tag(bytes, i) {
+                Some(tag_end) => {
+                    let tag = &sql[i..tag_end];
+                    i = sql[tag_end..]
+                        .find(tag)
+                        .map_or(bytes.len(), |end| tag_end + end + tag.len());
+                }
+                None => i += 1,
+            },
+            b';' if depth == 0 => {
+                push_statement(&mut statements, &sql[start..i]);
+                start = i + 1;
+                i += 1;
+                first_word = None;
+                compound = false;
+            }
+            c if is_word_byte(c) && !c.is_ascii_digit() && c != b'$' => {
+                let end = word_end(bytes, i);
+                let word = sql[i..end].to_ascii_uppercase();
+                if first_word.is_none() {
+                    first_word = Some(word.clone());
+                }
+                match word.as_str() {
+                    "TRIGGER" | "PROCEDURE" | "FUNCTION" | "EVENT"
+                        if first_word.as_deref() == Some("CREATE") =>
+                    {
+                        compound = true
+                    }
+                    "BEGIN" if compound => depth += 1,
+                    "CASE" if depth > 0 => depth += 1,
+                    // END IF, END LOOP and the like close blocks that were
+                    // never counted.
+                    "END"
+                        if depth > 0
+                            && !matches!(
+                                next_word(sql, end).as_str(),
+                                "IF" | "LOOP" | "WHILE" | "REPEAT"
+                            ) =>
+                    {
+                        depth -= 1
+                    }
+                    _ => {}
+                }
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+
+    push_statement(&mut statements, &sql[start..]);
+    statements
+}
+
+fn push_statement(statements: &mut Vec<String>, sql: &str) {
+    let sql = sql.trim();
+    // Skip empty statements and ones made only of comments.
+    if !skip_preamble(sql).is_empty() {
+        statements.push(sql.to_string());
+    }
+}
+
+fn is_word_byte(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_' || c == b'$' || c >= 0x80
+}
+
+fn word_end(bytes: &[u8], start: usize) -> usize {
+    (start..bytes.len())
+        .find(|&i| !is_word_byte(bytes[i]))
+        .unwrap_or(bytes.len())
+}
+
+fn next_word(sql: &str, from: usize) -> String {
+    sql[from..]
+        .trim_start()
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+/// Position just past the quoted string or identifier starting at `start`.
+fn skip_quoted(bytes: &[u8], start: usize, backslash: bool) -> usize {
+    let quote = bytes[start];
+    let mut i = start + 1;
+    while i < bytes.len() {
+        if backslash && bytes[i] == b'\\' {
+            i += 2;
+        } else if bytes[i] == quote {
+            // A doubled quote is an escaped quote.
+            if bytes.get(i + 1) != Some(&quote) {
+                return i + 1;
+            }
+            i += 2;
+        } else {
+            i += 1;
+        }
+    }
+    bytes.len()
+}
+
+fn skip_line(bytes: &[u8], start: usize) -> usize {
+    (start..bytes.len())
+        .find(|&i| bytes[i] == b'\n')
+        .map_or(bytes.len(), |i| i + 1)
+}
+
+/// End of the `$tag$` opening a dollar-quoted string at `start`, if any.
+/// Positional parameters like `$1` aren't tags.
+fn dollar_tag(bytes: &[u8], start: usize) -> Option<usize> {
+    if start > 0 && is_word_byte(bytes[start - 1]) {
+        return None;
+    }
+    let end = (start + 1..bytes.len()).find(|&i| !is_word_byte(bytes[i]) || bytes[i] == b'$')?;
+    let starts_with_digit = bytes.get(start + 1).is_some_and(|c| c.is_ascii_digit());
+    (bytes[end] == b'$' && !starts_with_digit).then_some(end + 1)
+}
+
+/// Whether the statement only reads data. Statements that aren't recognized
+/// count as writes.
+pub fn is_read_only(sql: &str) -> bool {
+    let changes_data = || {
+        ["INSERT", "UPDATE", "DELETE", "MERGE"]
+            .iter()
+            .any(|keyword| has_keyword(sql, keyword))
+    };
+    match leading_keyword(sql).as_str() {
+        "SELECT" | "VALUES" | "TABLE" | "SHOW" | "DESCRIBE" | "DESC" => true,
+        "WITH" => !changes_data(),
+        // EXPLAIN ANALYZE runs the statement it explains.
+        "EXPLAIN" => !has_keyword(sql, "ANALYZE") || !changes_data(),
+        "PRAGMA" => is_read_only_pragma(sql),
+        _ => false,
+    }
+}
+
+/// Whether every statement of a script only reads data.
+pub fn is_read_only_script(sql: &str, database_type: &DatabaseType) -> bool {
+    split(sql, database_type).iter().all(|s| is_read_only(s))
+}
+
+/// SQLite pragmas read a setting unless given a value, except for the
+/// introspection pragmas taking a table or index name.
+fn is_read_only_pragma(sql: &str) -> bool {
+    let body = skip_preamble(sql)["PRAGMA".len()..].trim();
+    if body.contains('=') {
+        return false;
+    }
+    let Some((name, _)) = body.split_once('(') else {
+        return true;
+    };
+    let name = name.rsplit('.').next().unwrap_or_default().trim();
+    matches!(
+        name.to_lowercase().as_str(),
+        "table_info"
+            | "table_xinfo"
+            | "table_list"
+            | "index_info"
+            | "index_xinfo"
+            | "index_list"
+            | "foreign_key_list"
+            | "foreign_key_check"
+            | "integrity_check"
+            | "quick_check"
+    )
+}
+
+/// Key terms and WHERE predicate of a `CREATE INDEX` statement, as written.
+/// SQLite only keeps the statement text, so this is how expression terms
+/// and partial-index predicates are recovered.
+pub fn index_definition(sql: &str) -> (Vec<String>, Option<String>) {
+    let bytes = sql.as_bytes();
+    // The key list opens at the first parenthesis outside the quoted names.
+    let mut open = 0;
+    while open < bytes.len() && bytes[open] != b'(' {
+        open = match bytes[open] {
+            b'\'' | b'"' | b'`' => skip_quoted(bytes, open, false),
+            b'[' => sql[open..]
+                .find(']')
+                .map_or(bytes.len(), |end| open + end + 1),
+            _ => open + 1,
+        };
+    }
+    if open == bytes.len() {
+        return (vec![], None);
+    }
+
+    let mut terms = Vec::new();
+    let mut depth = 0;
+    let mut start = open + 1;
+    let mut i = open;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' | b'"' | b'`' | b'[' => {
+                i = match bytes[i] {
+                    b'[' => sql[i..].find(']').map_or(bytes.len(), |end| i + end + 1),
+                    _ => skip_quoted(bytes, i, false),
+                };
+                continue;
+            }
+            b'(' => depth += 1,
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    terms.push(sql[start..i].trim().to_string());
+                    let rest = sql[i + 1..].trim();
+                    let predicate = rest
+                        .get(..5)
+                        .filter(|keyword| keyword.eq_ignore_ascii_case("WHERE"))
+                        .map(|_| strip_terminator(rest[5..].trim()).to_string());
+                    return (terms, predicate);
+                }
+            }
+            b',' if depth == 1 => {
+                terms.push(sql[start..i].trim().to_string());
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    (terms, None)
+}
+
+/// Upper-cased words of the statement, skipping quoted strings and
+/// identifiers.
+fn words(sql: &str) -> Vec<String> {
+    let bytes = sql.as_bytes();
+    let mut words = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' | b'"' | b'`' => i = skip_quoted(bytes, i, false),
+            b'[' => i = sql[i..].find(']').map_or(bytes.len(), |end| i + end + 1),
+            c if is_word_byte(c) => {
+                let end = word_end(bytes, i);
+                words.push(sql[i..end].to_ascii_uppercase());
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+    words
+}
+
+/// Whether the statement is a CREATE TRIGGER, allowing for modifiers such
+/// as OR REPLACE, TEMP or a MySQL DEFINER clause before TRIGGER.
+pub fn is_create_trigger(sql: &str) -> bool {
+    let words = words(skip_preamble(sql));
+    if words.first().map(String::as_str) != Some("CREATE") {
+        return false;
+    }
+    words
+        .iter()
+        .find(|word| {
+            matches!(
+                word.as_str(),
+                "TRIGGER"
+                    | "TABLE"
+                    | "VIEW"
+                    | "INDEX"
+                    | "FUNCTION"
+                    | "PROCEDURE"
+                    | "EVENT"
+                    | "SEQUENCE"
+                    | "SCHEMA"
+                    | "DATABASE"
+                    | "TYPE"
+            )
+        })
+        .is_some_and(|word| word == "TRIGGER")
+}
+
+/// Timing and event of a `CREATE TRIGGER` statement. SQLite triggers fire
+/// BEFORE unless told otherwise.
+pub fn trigger_timing_event(sql: &str) -> (String, String) {
+    let mut timing = "BEFORE";
+    let mut event = String::new();
+    for word in words(sql) {
+        match word.as_str() {
+            "BEFORE" => timing = "BEFORE",
+            "AFTER" => timing = "AFTER",
+            "INSTEAD" => timing = "INSTEAD OF",
+            "INSERT" | "UPDATE" | "DELETE" => {
+                event = word;
+                break;
+            }
+            _ => {}
+        }
+    }
+    (timing.to_string(), event)
+}
+
+/// Everything from the column list of a `CREATE TABLE` statement on, which
+/// is what remains to give the same table another name.
+pub fn create_table_body(sql: &str) -> Option<&str> {
+    let bytes = sql.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' | b'"' | b'`' => i = skip_quoted(bytes, i, false),
+            b'[' => i = sql[i..].find(']').map_or(bytes.len(), |end| i + end + 1),
+            b'(' => return Some(strip_terminator(sql[i..].trim_end())),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Column definitions and table constraints of a `CREATE TABLE` body as
+/// returned by `create_table_body`, followed by the table options after the
+/// column list, such as `WITHOUT ROWID`.
+pub fn table_definitions(body: &str) -> (Vec<String>, String) {
+    let bytes = body.as_bytes();
+    let mut definitions = Vec::new();
+    let mut depth = 0;
+    let mut start = 1;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' | b'"' | b'`' => {
+                i = skip_quoted(bytes, i, false);
+                continue;
+            }
+            b'[' => {
+                i = body[i..].find(']').map_or(bytes.len(), |end| i + end + 1);
+                continue;
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                i = skip_line(bytes, i);
+                continue;
+            }
+            b'(' => depth += 1,
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    definitions.push(body[start..i].trim().to_string());
+                    return (definitions, body[i + 1..].trim().to_string());
+                }
+            }
+            b',' if depth == 1 => {
+                definitions.push(body[start..i].trim().to_string());
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    (definitions, String::new())
+}
+
+/// Name of the column a `CREATE TABLE` definition declares, or `None` for a
+/// table constraint.
+pub fn column_name(definition: &str) -> Option<String> {
+    let constraint = matches!(
+        leading_keyword(definition).as_str(),
+        "CONSTRAINT" | "PRIMARY" | "UNIQUE" | "CHECK" | "FOREIGN"
+    );
+    if constraint {
+        return None;
+    }
+    identifiers(definition)
+        .into_iter()
+        .next()
+        .map(|(_, _, name)| name)
+}
+
+/// Name and type of a column definition, followed by its constraint clauses
+/// such as `NOT NULL` or `DEFAULT (1 + 2)`, each as written.
+pub fn column_clauses(definition: &str) -> (String, Vec<String>) {
+    let spans = clause_spans(definition);
+    let Some(&(first, _)) = spans.first() else {
+        return (definition.trim().to_string(), vec![]);
+    };
+    let clauses = spans
+        .iter()
+        .map(|&(start, end)| definition[start..end].trim().to_string())
+        .collect();
+    (definition[..first].trim().to_string(), clauses)
+}
+
+/// Spans of the constraint clauses of a column definition.
+fn clause_spans(definition: &str) -> Vec<(usize, usize)> {
+    const CLAUSES: [&str; 17] = [
+        "CONSTRAINT",
+        "PRIMARY",
+        "NOT",
+        "NULL",
+        "UNIQUE",
+        "CHECK",
+        "DEFAULT",
+        "COLLATE",
+        "REFERENCES",
+        "GENERATED",
+        "AS",
+        "AUTO_INCREMENT",
+        "COMMENT",
+        "CHARACTER",
+        "ON",
+        "VISIBLE",
+        "INVISIBLE",
+    ];
+
+    let bytes = definition.as_bytes();
+    let mut starts = Vec::new();
+    let mut depth = 0;
+    let mut previous = String::new();
+    // Clauses start after the column name.
+    let mut i = identifiers(definition)
+        .first()
+        .map_or(0, |&(_, end, _)| end);
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' | b'"' | b'`' => {
+                i = skip_quoted(bytes, i, false);
+                previous.clear();
+                continue;
+            }
+            b'[' => {
+                i = definition[i..]
+                    .find(']')
+                    .map_or(bytes.len(), |end| i + end + 1);
+                previous.clear();
+                continue;
+            }
+            b'(' => depth += 1,
+            b')' => depth -= 1,
+            c if is_word_byte(c) => {
+                let end = word_end(bytes, i);
+                let word = definition[i..end].to_ascii_uppercase();
+                // NULL in NOT NULL, DEFAULT NULL or SET NULL, and AS in
+                // GENERATED ALWAYS AS, continue the clause before them.
+                let continues = matches!(previous.as_str(), "NOT" | "SET" | "DEFAULT" | "ALWAYS")
+                    || (word == "CHARACTER" && next_word(definition, end) != "SET");
+                if depth == 0 && CLAUSES.contains(&word.as_str()) && !continues {
+                    starts.push(i);
+                }
+                previous = word;
+                i = end;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    starts
+        .iter()
+        .zip(starts.iter().skip(1).chain(std::iter::once(&bytes.len())))
+        .map(|(&start, &end)| (start, end))
+        .collect()
+}
+
+/// Whether the identifier `name` appears in `sql`, quoted or not.
+pub fn mentions_identifier(sql: &str, name: &str) -> bool {
+    identifiers(sql)
+        .iter()
+        .any(|(_, _, identifier)| identifier.eq_ignore_ascii_case(name))
+}
+
+/// References to the column `name` in an expression, each with the table
+/// or alias qualifying it. Function names, type names after AS, collation
+/// and constraint names, qualifiers and the ASC and DESC keywords are not
+/// column references.
+pub fn column_references(sql: &str, name: &str) -> Vec<(usize, usize, Option<String>)> {
+    let identifiers = identifiers(sql);
+    let mut references = Vec::new();
+    for (k, (start, end, identifier)) in identifiers.iter().enumerate() {
+        if !identifier.eq_ignore_ascii_case(name) {
+            continue;
+        }
+        let after = sql[*end..].trim_start();
+        let keyword = is_word_byte(sql.as_bytes()[*start])
+            && matches!(identifier.to_ascii_uppercase().as_str(), "ASC" | "DESC");
+        if after.starts_with('(') || after.starts_with('.') || keyword {
+            continue;
+        }
+        let previous = k.checked_sub(1).map(|p| &identifiers[p]);
+        let qualifier = match previous {
+            Some((_, previous_end, previous)) => match sql[*previous_end..*start].trim() {
+                "." => Some(previous.clone()),
+                "" if matches!(
+                    previous.to_ascii_uppercase().as_str(),
+                    "AS" | "COLLATE" | "CONSTRAINT" | "CONFLICT"
+                ) =>
+                {
+                    continue;
+                }
+                _ => None,
+            },
+            None => None,
+        };
+        references.push((*start, *end, qualifier));
+    }
+    references
+}
+
+/// Spans of a `CREATE TABLE` definition of `table` that refer to its column
+/// `column`: the name a column definition declares, references in CHECK and
+/// generated expressions, key column lists, and REFERENCES lists pointing
+/// back at `table`.
+fn definition_spans(definition: &str, table: &str, column: &str) -> Vec<(usize, usize)> {
+    let own = |qualifier: &Option<String>| {
+        qualifier
+            .as_ref()
+            .is_none_or(|q| q.eq_ignore_ascii_case(table))
+    };
+    let expression = |start: usize, end: usize| {
+        column_references(&definition[start..end], column)
+            .into_iter()
+            .filter(|(_, _, qualifier)| own(qualifier))
+            .map(move |(s, e, _)| (start + s, start + e))
+    };
+
+    let mut spans = Vec::new();
+    let references = if column_name(definition).is_some() {
+        let name = identifiers(definition).into_iter().next();
+        if let Some((start, end, _)) = name.filter(|(_, _, name)| name.eq_ignore_ascii_case(column))
+        {
+            spans.push((start, end));
+        }
+        let mut references = None;
+        for (start, end) in clause_spans(definition) {
+            match leading_keyword(&definition[start..end]).as_str() {
+                "CHECK" | "GENERATED" | "AS" => spans.extend(expression(start, end)),
+                "REFERENCES" => references = Some((start, end)),
+                _ => {}
+            }
+        }
+        references
+    } else {
+        // A table constraint: key columns, then the columns referred to.
+        let start = keyword_position(definition, "REFERENCES");
+        spans.extend(expression(0, start.unwrap_or(definition.len())));
+        start.map(|start| (start, definition.len()))
+    };
+
+    // Only a list pointing back at this table names its columns.
+    if let Some((start, end)) = references {
+        let clause = &definition[start..end];
+        let names = identifiers(clause);
+        if let Some((_, target_end, target)) = names.get(1)
+            && target.eq_ignore_ascii_case(table)
+            && clause[*target_end..].trim_start().starts_with('(')
+        {
+            let close = clause[*target_end..]
+                .find(')')
+                .map_or(clause.len(), |close| target_end + close);
+            spans.extend(
+                names
+                    .iter()
+                    .skip(2)
+                    .filter(|(s, _, name)| *s < close && name.eq_ignore_ascii_case(column))
+                    .map(|(s, e, _)| (start + s, start + e)),
+            );
+        }
+    }
+    spans.sort();
+    spans
+}
+
+/// Name a table constraint is given with CONSTRAINT, if any.
+pub fn constraint_name(definition: &str) -> Option<String> {
+    if leading_keyword(definition) != "CONSTRAINT" {
+        return None;
+    }
+    identifiers(definition)
+        .into_iter()
+        .nth(1)
+        .map(|(_, _, name)| name)
+}
+
+/// A `CREATE TABLE` definition of `table` with its column `old` renamed to
+/// `new`, given quoted. Type names, collations and lists referring to other
+/// tables are left alone.
+pub fn rename_in_definition(definition: &str, table: &str, old: &str, new: &str) -> String {
+    replace_spans(definition, &definition_spans(definition, table, old), new)
+}
+
+/// Whether a `CREATE TABLE` definition of `table` other than the column's
+/// own uses the column `column`.
+pub fn definition_uses_column(definition: &str, table: &str, column: &str) -> bool {
+    !definition_spans(definition, table, column).is_empty()
+}
+
+/// A `CREATE INDEX` statement with the column `old` renamed to `new`, given
+/// quoted, in its key terms and predicate. Everything before the column
+/// list, where the index and table are named, is left alone.
+pub fn rename_in_index(sql: &str, old: &str, new: &str) -> String {
+    let open = sql.find('(').unwrap_or(sql.len());
+    let spans: Vec<(usize, usize)> = column_references(&sql[open..], old)
+        .into_iter()
+        .filter(|(_, _, qualifier)| qualifier.is_none())
+        .map(|(start, end, _)| (open + start, open + end))
+        .collect();
+    replace_spans(sql, &spans, new)
+}
+
+/// Spans of a `CREATE TRIGGER` on `table` that refer to its column
+/// `column`: the UPDATE OF list and names qualified by NEW, OLD or the table.
+/// `None` when the column is also named without a qualifier, which in the
+/// trigger body may mean a column of another table.
+fn trigger_spans(sql: &str, table: &str, column: &str) -> Option<Vec<(usize, usize)>> {
+    let names = identifiers(sql);
+    let is = |k: usize, word: &str| {
+        names.get(k).is_some_and(|(start, _, name)| {
+            is_word_byte(sql.as_bytes()[*start]) && name.eq_ignore_ascii_case(word)
+        })
+    };
+    let mut spans = Vec::new();
+    if let Some(of) = (1..names.len()).find(|&k| is(k - 1, "UPDATE") && is(k, "OF")) {
+        spans.extend(
+            names[of + 1..]
+                .iter()
+                .take_while(|(start, _, name)| {
+                    !(is_word_byte(sql.as_bytes()[*start]) && name.eq_ignore_ascii_case("ON"))
+                })
+                .filter(|(_, _, name)| name.eq_ignore_ascii_case(column))
+                .map(|(start, end, _)| (*start, *end)),
+        );
+    }
+    for (start, end, qualifier) in column_references(sql, column) {
+        match qualifier {
+            Some(q)
+                if ["NEW", "OLD", table]
+                    .iter()
+                    .any(|t| q.eq_ignore_ascii_case(t)) =>
+            {
+                spans.push((start, end))
+            }
+            Some(_) => {}
+            None if spans.contains(&(start, end)) => {}
+            None => return None,
+        }
+    }
+    spans.sort();
+    Some(spans)
+}
+
+/// A `CREATE TRIGGER` on `table` with its column `old` renamed to `new`,
+/// given quoted, or `None` when the trigger names the column in a way that
+/// can't be told apart from another table's column.
+pub fn rename_in_trigger(sql: &str, table: &str, old: &str, new: &str) -> Option<String> {
+    trigger_spans(sql, table, old).map(|spans| replace_spans(sql, &spans, new))
+}
+
+/// Whether a `CREATE TRIGGER` on `table` uses, or may use, its column
+/// `column`.
+pub fn trigger_uses_column(sql: &str, table: &str, column: &str) -> bool {
+    trigger_spans(sql, table, column).is_none_or(|spans| !spans.is_empty())
+}
+
+/// `sql` with the spans, in order, replaced by `new`.
+fn replace_spans(sql: &str, spans: &[(usize, usize)], new: &str) -> String {
+    let mut replaced = String::new();
+    let mut last = 0;
+    for &(start, end) in spans {
+        replaced.push_str(&sql[last..start]);
+        replaced.push_str(new);
+        last = end;
+    }
+    replaced.push_str(&sql[last..]);
+    replaced
+}
+
+/// Position of `keyword` as a word outside parentheses, quoted strings and
+/// identifiers.
+fn keyword_position(sql: &str, keyword: &str) -> Option<usize> {
+    let bytes = sql.as_bytes();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' | b'"' | b'`' => i = skip_quoted(bytes, i, false),
+            b'[' => i = sql[i..].find(']').map_or(bytes.len(), |end| i + end + 1),
+            b'(' => {
+                depth += 1;
+                i += 1;
+            }
+            b')' => {
+                depth -= 1;
+                i += 1;
+            }
+            c if is_word_byte(c) => {
+                let end = word_end(bytes, i);
+                if depth == 0 && sql[i..end].eq_ignore_ascii_case(keyword) {
+                    return Some(i);
+                }
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Words and quoted identifiers of `sql` with their spans, unquoted. String
+/// literals, numbers and comments are skipped.
+fn identifiers(sql: &str) -> Vec<(usize, usize, String)> {
+    let bytes = sql.as_bytes();
+    let mut identifiers = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' => i = skip_quoted(bytes, i, false),
+            // An unterminated quote runs to the end of the input.
+            quote @ (b'"' | b'`') => {
+                let end = skip_quoted(bytes, i, false);
+                let terminated = end >= i + 2 && bytes[end - 1] == quote;
+                let inner = if terminated {
+                    &sql[i + 1..end - 1]
+                } else {
+                    &sql[i + 1..]
+                };
+                let quote = (quote as char).to_string();
+                identifiers.push((i, end, inner.replace(&quote.repeat(2), &quote)));
+                i = end;
+            }
+            b'[' => {
+                let (end, name) = match sql[i..].find(']') {
+                    Some(close) => (i + close + 1, &sql[i + 1..i + close]),
+                    None => (bytes.len(), &sql[i + 1..]),
+                };
+                identifiers.push((i, end, name.to_string()));
+                i = end;
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => i = skip_line(bytes, i),
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = sql[i + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| i + end + 4);
+            }
+            c if is_word_byte(c) => {
+                let end = word_end(bytes, i);
+                if !c.is_ascii_digit() {
+                    identifiers.push((i, end, sql[i..end].to_string()));
+                }
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+    identifiers
+}
+
+/// The query of a `CREATE VIEW` statement: everything after the first AS
+/// outside parentheses, which follows the name and optional column list.
+pub fn view_query(sql: &str) -> &str {
+    let bytes = sql.as_bytes();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' | b'"' | b'`' => i = skip_quoted(bytes, i, false),
+            b'[' => i = sql[i..].find(']').map_or(bytes.len(), |end| i + end + 1),
+            b'(' => {
+                depth += 1;
+                i += 1;
+            }
+            b')' => {
+                depth -= 1;
+                i += 1;
+            }
+            c if is_word_byte(c) => {
+                let end = word_end(bytes, i);
+                if depth == 0 && sql[i..end].eq_ignore_ascii_case("AS") {
+                    return strip_terminator(sql[end..].trim());
+                }
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+    sql
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split_sqlite(sql: &str) -> Vec<String> {
+        split(sql, &DatabaseType::Sqlite)
+    }
+
+    #[test]
+    fn split_ignores_semicolons_in_quotes_and_comments() {
+        assert_eq!(
+            split_sqlite("SELECT 'a;b'; SELECT \"c;d\" FROM t;"),
+            ["SELECT 'a;b'", "SELECT \"c;d\" FROM t"]
+        );
+        assert_eq!(
+            split_sqlite("SELECT 1; -- one; two\nSELECT 2 /* ; */;"),
+            ["SELECT 1", "-- one; two\nSELECT 2 /* ; */"]
+        );
+        assert_eq!(split_sqlite("SELECT 'it''s;'; "), ["SELECT 'it''s;'"]);
+        assert!(split_sqlite(" ; -- nothing\n;").is_empty());
+    }
+
+    #[test]
+    fn split_uses_backslash_escapes_on_mysql() {
+        assert_eq!(
+            split(r"SELECT 'a\';b'; SELECT 2 # c;", &DatabaseType::Mysql),
+            [r"SELECT 'a\';b'", "SELECT 2 # c;"]
+        );
+        assert_eq!(
+            split(r"SELECT E'a\';b'; SELECT 2", &DatabaseType::Postgres),
+            [r"SELECT E'a\';b'", "SELECT 2"]
+        );
+    }
+
+    #[test]
+    fn split_keeps_dollar_quoted_bodies() {
+        let function = "CREATE FUNCTION f() RETURNS int AS $body$ BEGIN RETURN 1; END $body$ \
+                        LANGUAGE plpgsql";
+        assert_eq!(
+            split(&format!("{}; SELECT $1", function), &DatabaseType::Postgres),
+            [function, "SELECT $1"]
+        );
+    }
+
+    #[test]
+    fn split_keeps_trigger_bodies() {
+        let trigger = "CREATE TRIGGER t AFTER INSERT ON a BEGIN \
+                       UPDATE b SET n = CASE WHEN n > 0 THEN n END; \
+                       DELETE FROM c; END";
+        assert_eq!(
+            split_sqlite(&format!("{};\nSELECT 1", trigger)),
+            [trigger, "SELECT 1"]
+        );
+        // A transaction's BEGIN isn't a body.
+        assert_eq!(
+            split_sqlite("BEGIN; INSERT INTO a VALUES (1); END;"),
+            ["BEGIN", "INSERT INTO a VALUES (1)", "END"]
+        );
+    }
+
+    #[test]
+    fn read_only_statements() {
+        for sql in [
+            "SELECT 1",
+            "  /* c */ (SELECT 1)",
+            "WITH x AS (SELECT 1) SELECT * FROM x",
+            "EXPLAIN SELECT 1",
+            "EXPLAIN ANALYZE SELECT 1",
+            "SHOW TABLES",
+            "PRAGMA table_info(users)",
+            "PRAGMA main.index_list('t')",
+            "PRAGMA journal_mode",
+            "SELECT 'DELETE'",
+        ] {
+            assert!(is_read_only(sql), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn writing_statements() {
+        for sql in [
+            "WITH x AS (SELECT 1) DELETE FROM t",
+            "WITH d AS (DELETE FROM t RETURNING *) SELECT * FROM d",
+            "EXPLAIN ANALYZE DELETE FROM t",
+            "PRAGMA journal_mode = WAL",
+            "PRAGMA foreign_keys=off",
+            "PRAGMA wal_checkpoint(TRUNCATE)",
+            "INSERT INTO t VALUES (1)",
+            "VACUUM",
+            "-- SELECT\nDROP TABLE t",
+        ] {
+            assert!(!is_read_only(sql), "{}", sql);
+        }
+        assert!(!is_read_only_script(
+            "SELECT 1; DELETE FROM t",
+            &DatabaseType::Sqlite
+        ));
+        assert!(is_read_only_script(
+            "SELECT ';DELETE'",
+            &DatabaseType::Sqlite
+        ));
+    }
+
+    #[test]
+    fn unterminated_quotes_run_to_the_end() {
+        assert!(mentions_identifier("\"café", "café"));
+        assert!(mentions_identifier("[café", "café"));
+        assert!(mentions_identifier("`é", "é"));
+        assert!(mentions_identifier("a \"", ""));
+        assert!(mentions_identifier("\"a\"\"b\"", "a\"b"));
+    }
+
+    #[test]
+    fn create_trigger_allows_modifiers() {
+        assert!(is_create_trigger(
+            "CREATE TEMP TRIGGER t AFTER INSERT ON a BEGIN END"
+        ));
+        assert!(is_create_trigger(
+            "CREATE OR REPLACE TRIGGER t BEFORE UPDATE ON a FOR EACH ROW EXECUTE FUNCTION f()"
+        ));
+        assert!(is_create_trigger(
+            "/* tr */ CREATE DEFINER=`root`@`%` TRIGGER t BEFORE DELETE ON a FOR EACH ROW SET @n = 1"
+        ));
+        assert!(!is_create_trigger("CREATE TABLE trigger_log (id INT)"));
+        assert!(!is_create_trigger("DROP TRIGGER t"));
+    }
+
+    #[test]
+    fn trigger_timing_defaults_to_before() {
+        let timing_event = |sql| {
+            let (timing, event) = trigger_timing_event(sql);
+            format!("{} {}", timing, event)
+        };
+        assert_eq!(
+            timing_event("CREATE TRIGGER t AFTER UPDATE OF a ON t BEGIN END"),
+            "AFTER UPDATE"
+        );
+        assert_eq!(
+            timing_event("CREATE TRIGGER t DELETE ON t BEGIN END"),
+            "BEFORE DELETE"
+        );
+        assert_eq!(
+            timing_event("CREATE TRIGGER t INSTEAD OF INSERT ON v BEGIN END"),
+            "INSTEAD OF INSERT"
+        );
+        // Quoted names that look like events don't count.
+        assert_eq!(
+            timing_event("CREATE TRIGGER \"insert\" AFTER DELETE ON t BEGIN END"),
+            "AFTER DELETE"
+        );
+    }
+
+    #[test]
+    fn without_casts_strips_outer_casts_and_parentheses() {
+        assert_eq!(without_casts("'x'::text"), "'x'");
+        assert_eq!(without_casts(" ('a::b'::character varying) "), "'a::b'");
+        assert_eq!(without_casts("(0)::bigint"), "0");
+        assert_eq!(without_casts("NULL::\"char\"[]"), "NULL");
+        assert_eq!(
+            without_casts("nextval('t_id_seq'::regclass)"),
+            "nextval('t_id_seq'::regclass)"
+        );
+        assert_eq!(without_casts("(a) + (b)"), "(a) + (b)");
+        assert_eq!(without_casts("(datetime('now'))"), "datetime('now')");
+        assert_eq!(without_casts(""), "");
+    }
+
+    #[test]
+    fn index_definition_skips_quoted_names() {
+        assert_eq!(
+            index_definition(
+                "CREATE UNIQUE INDEX \"ix (a)\" ON [t(1)] (lower(name), b DESC) WHERE b > 0;"
+            ),
+            (
+                vec!["lower(name)".to_string(), "b DESC".to_string()],
+                Some("b > 0".to_string())
+            )
+        );
+        assert_eq!(index_definition("CREATE INDEX ix ON t"), (vec![], None));
+    }
+
+    #[test]
+    fn rename_leaves_type_names_alone() {
+        assert_eq!(
+            rename_in_definition("text TEXT NOT NULL", "t", "text", "\"body\""),
+            "\"body\" TEXT NOT NULL"
+        );
+        assert_eq!(
+            rename_in_definition("d date CHECK (date(d) > '2000')", "t", "date", "\"day\""),
+            "d date CHECK (date(d) > '2000')"
+        );
+        assert_eq!(
+            rename_in_definition(
+                "total REAL GENERATED ALWAYS AS (CAST(text AS text) * 2)",
+                "t",
+                "text",
+                "\"body\""
+            ),
+            "total REAL GENERATED ALWAYS AS (CAST(\"body\" AS text) * 2)"
+        );
+    }
+
+    #[test]
+    fn rename_only_follows_references_to_the_same_table() {
+        assert_eq!(
+            rename_in_definition("owner INT REFERENCES users(id)", "t", "id", "\"key\""),
+            "owner INT REFERENCES users(id)"
+        );
+        assert_eq!(
+            rename_in_definition("parent INT REFERENCES t(id)", "t", "id", "\"key\""),
+            "parent INT REFERENCES t(\"key\")"
+        );
+        assert_eq!(
+            rename_in_definition(
+                "FOREIGN KEY (id) REFERENCES other(id)",
+                "t",
+                "id",
+                "\"key\""
+            ),
+            "FOREIGN KEY (\"key\") REFERENCES other(id)"
+        );
+        assert_eq!(
+            rename_in_definition("CONSTRAINT id UNIQUE (id, b)", "t", "id", "\"key\""),
+            "CONSTRAINT id UNIQUE (\"key\", b)"
+        );
+    }
+
+    #[test]
+    fn rename_in_index_keeps_collations_and_order() {
+        assert_eq!(
+            rename_in_index(
+                "CREATE INDEX nocase ON t(nocase COLLATE nocase DESC) WHERE nocase <> ''",
+                "nocase",
+                "\"name\""
+            ),
+            "CREATE INDEX nocase ON t(\"name\" COLLATE nocase DESC) WHERE \"name\" <> ''"
+        );
+    }
+
+    #[test]
+    fn rename_in_trigger_needs_qualified_names() {
+        let trigger = "CREATE TRIGGER log AFTER UPDATE OF a ON t BEGIN \
+                       INSERT INTO audit (value) VALUES (NEW.a); END";
+        assert_eq!(
+            rename_in_trigger(trigger, "t", "a", "\"b\"").as_deref(),
+            Some(
+                "CREATE TRIGGER log AFTER UPDATE OF \"b\" ON t BEGIN \
+                 INSERT INTO audit (value) VALUES (NEW.\"b\"); END"
+            )
+        );
+        let unqualified = "CREATE TRIGGER log AFTER INSERT ON t BEGIN \
+                           UPDATE t SET a = 1; END";
+        assert_eq!(rename_in_trigger(unqualified, "t", "a", "\"b\""), None);
+        assert!(trigger_uses_column(unqualified, "t", "a"));
+        assert!(!trigger_uses_column(trigger, "t", "c"));
+    }
+
+    #[test]
+    fn constraint_name_is_read_after_constraint() {
+        assert_eq!(
+            constraint_name("CONSTRAINT \"pair\" UNIQUE (a, b)").as_deref(),
+            Some("pair")
+        );
+        assert_eq!(constraint_name("UNIQUE (a, b)"), None);
+    }
+
+    #[test]
+    fn definition_uses_column_ignores_type_names() {
+        assert!(!definition_uses_column("note text", "t", "text"));
+        assert!(definition_uses_column(
+            "n INT CHECK (n < text)",
+            "t",
+            "text"
+        ));
+        assert!(definition_uses_column("PRIMARY KEY (a, text)", "t", "text"));
+    }
+}